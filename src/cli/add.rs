@@ -1,5 +1,4 @@
 use anyhow::{bail, Result};
-use octocrab::params::State;
 
 use std::ffi::OsString;
 
@@ -7,21 +6,41 @@ use git2::Repository;
 
 use crate::{
     utils::{
-        cli::{add_branch_to_repo, add_worktree_to_repo},
-        github::pr::add_workspace_by_pull_requests,
+        cli::{
+            add_branch_to_repo, add_detached_worktree_to_repo, add_worktree_to_repo,
+            checkout_detached_in_repo,
+        },
+        forge::{
+            pr::{add_workspace_by_pull_requests, print_pr_results_table, PrResultStatus},
+            ChangeRequestState,
+        },
+        git::fetch::FetchSettings,
     },
-    PRKind, PrSelection,
+    OutputFormat, PRKind, PrSelection,
 };
 
-pub(crate) fn add_sub_command(repo: Repository, name: OsString) -> Result<()> {
-    let (command, _) = if repo.is_bare() || repo.is_worktree() {
-        if name.to_string_lossy().contains('/') {
-            bail!("Cannot add a worktree with a '/' in the name")
+pub(crate) fn add_sub_command(
+    repo: Repository,
+    name: Option<OsString>,
+    detach: Option<String>,
+    fetch_settings: FetchSettings,
+) -> Result<()> {
+    let name = name.map(|os_str| os_str.to_string_lossy().into_owned());
+
+    let (command, _) = match (name, detach) {
+        (name, Some(rev)) if repo.is_bare() || repo.is_worktree() => {
+            add_detached_worktree_to_repo(&repo, name.as_deref(), &rev, &fetch_settings)?
         }
+        (_, Some(rev)) => checkout_detached_in_repo(&repo, &rev, &fetch_settings)?,
+        (Some(name), None) if repo.is_bare() || repo.is_worktree() => {
+            if name.contains('/') {
+                bail!("Cannot add a worktree with a '/' in the name")
+            }
 
-        add_worktree_to_repo(&repo, name)?
-    } else {
-        add_branch_to_repo(&repo, name)?
+            add_worktree_to_repo(&repo, name, &fetch_settings)?
+        }
+        (Some(name), None) => add_branch_to_repo(&repo, name, &fetch_settings)?,
+        (None, None) => bail!("A name or a revision to detach at is required"),
     };
 
     println!("{}", command);
@@ -29,11 +48,41 @@ pub(crate) fn add_sub_command(repo: Repository, name: OsString) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn add_from_pr_sub_command(
     repo: Repository,
-    pr_state: State,
+    pr_state: ChangeRequestState,
     pr_kind: PRKind,
     pr_selection: PrSelection,
+    pr_numbers: Vec<u64>,
+    fetch_settings: FetchSettings,
+    output: OutputFormat,
+    jobs: usize,
 ) -> Result<()> {
-    add_workspace_by_pull_requests(&repo, pr_state, pr_kind, pr_selection).await
+    let pr_results = add_workspace_by_pull_requests(
+        &repo,
+        pr_state,
+        pr_kind,
+        pr_selection,
+        pr_numbers,
+        fetch_settings,
+        jobs,
+    )
+    .await?;
+
+    match output {
+        OutputFormat::Table => print_pr_results_table(&pr_results),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&pr_results)?),
+    }
+
+    let failed = pr_results
+        .iter()
+        .filter(|pr_result| pr_result.status == PrResultStatus::Failed)
+        .count();
+
+    if failed > 0 {
+        bail!("{} of {} PRs failed", failed, pr_results.len());
+    }
+
+    Ok(())
 }
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::{
+    cli::table::print_table,
+    git::{
+        alias::{add_alias, get_aliases, remove_alias},
+        worktree::normalize_workspace_name,
+    },
+};
+
+pub(crate) fn alias_add_sub_command(
+    repo: Repository,
+    alias: String,
+    worktree_name: String,
+) -> Result<()> {
+    let worktree_name = normalize_workspace_name(&worktree_name);
+
+    add_alias(&repo, &alias, &worktree_name)?;
+
+    println!("`{}` now points to `{}`", alias, worktree_name);
+
+    Ok(())
+}
+
+pub(crate) fn alias_list_sub_command(repo: Repository) -> Result<()> {
+    let aliases = get_aliases(&repo)?;
+
+    if aliases.is_empty() {
+        println!("No aliases defined");
+
+        return Ok(());
+    }
+
+    let rows = aliases
+        .into_iter()
+        .map(|(alias, worktree_name)| [alias, worktree_name])
+        .collect::<Vec<_>>();
+
+    print_table(["ALIAS", "WORKTREE"], &rows);
+
+    Ok(())
+}
+
+pub(crate) fn alias_remove_sub_command(repo: Repository, alias: String) -> Result<()> {
+    let worktree_name = remove_alias(&repo, &alias)?;
+
+    println!("Removed `{}`, it pointed to `{}`", alias, worktree_name);
+
+    Ok(())
+}
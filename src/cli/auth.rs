@@ -0,0 +1,71 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+use git2::Repository;
+
+use crate::utils::{
+    forge::{detect_forge_kind, get_remote_url, github::get_github_api, ForgeKind},
+    github::{
+        auth::GITHUB_HOST,
+        common::{get_token_scopes, retrieve_github_access_token, setup_octocrab_with_token},
+    },
+};
+
+/// GitHub host and API URL of the repository, github.com when it is not hosted on GitHub
+fn get_host_and_api_url(repo: Option<&Repository>) -> (String, Option<String>) {
+    repo.and_then(|repo| {
+        let remote = get_remote_url(repo).ok()?;
+
+        matches!(detect_forge_kind(repo, &remote), Ok(ForgeKind::Github))
+            .then(|| get_github_api(repo, &remote))
+    })
+    .unwrap_or_else(|| (GITHUB_HOST.to_string(), None))
+}
+
+pub(crate) async fn auth_status_sub_command(repo: Option<Repository>) -> Result<()> {
+    let (host, api_url) = get_host_and_api_url(repo.as_ref());
+
+    let Some(access_token) = retrieve_github_access_token(&host) else {
+        if host == GITHUB_HOST {
+            bail!(
+                "No GitHub token found. Set WORKTREE_CLI_GITHUB_TOKEN, GH_TOKEN or GITHUB_TOKEN, log in with `gh auth login` or store credentials for {} in a git credential helper",
+                host
+            );
+        }
+
+        bail!(
+            "No GitHub token found. Set WORKTREE_CLI_GITHUB_TOKEN, log in with `gh auth login --hostname {}` or store credentials for {} in a git credential helper",
+            host,
+            host
+        );
+    };
+
+    let (octocrab, rate) =
+        setup_octocrab_with_token(Some(&access_token), api_url.as_deref()).await?;
+
+    println!("Host: {}", host);
+    println!("Token source: {}", access_token.source);
+
+    match get_token_scopes(&octocrab).await {
+        Ok(Some(scopes)) if !scopes.is_empty() => println!("Scopes: {}", scopes.join(", ")),
+        Ok(_) => println!("Scopes: none reported"),
+        Err(e) => warn!("Failed to get token scopes: {}", e),
+    }
+
+    if let Some(rate) = rate {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        println!(
+            "Rate limit: {}/{} used, {} remaining (resets in {}s)",
+            rate.used,
+            rate.limit,
+            rate.remaining,
+            rate.reset.saturating_sub(now)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use anyhow::{bail, Result};
+use git2::Repository;
+
+use crate::utils::{
+    cli::table::print_table,
+    doctor::{run_checks, CheckStatus, DoctorSettings},
+};
+
+pub(crate) async fn doctor_sub_command(repo: Repository, fix: bool, offline: bool) -> Result<()> {
+    let check_results = run_checks(&repo, &DoctorSettings { fix, offline }).await?;
+
+    let rows = check_results
+        .iter()
+        .map(|check_result| {
+            [
+                check_result.check.clone(),
+                check_result.status.to_string(),
+                check_result.detail.clone(),
+            ]
+        })
+        .collect::<Vec<[String; 3]>>();
+
+    print_table(["CHECK", "STATUS", "DETAILS"], &rows);
+
+    let fixes = check_results
+        .iter()
+        .filter_map(|check_result| {
+            check_result
+                .fix
+                .as_ref()
+                .map(|fix| format!("  {}: {}", check_result.check, fix))
+        })
+        .collect::<Vec<String>>();
+
+    if !fixes.is_empty() {
+        println!("\nSuggested fixes:\n{}", fixes.join("\n"));
+    }
+
+    if check_results
+        .iter()
+        .any(|check_result| check_result.fixable)
+    {
+        println!("\nRun with --fix to apply the safe fixes");
+    }
+
+    let count = |status: CheckStatus| {
+        check_results
+            .iter()
+            .filter(|check_result| check_result.status == status)
+            .count()
+    };
+
+    println!(
+        "\n{} ok, {} fixed, {} warnings, {} errors",
+        count(CheckStatus::Ok),
+        count(CheckStatus::Fixed),
+        count(CheckStatus::Warning),
+        count(CheckStatus::Error)
+    );
+
+    if count(CheckStatus::Error) > 0 {
+        bail!("{} check(s) failed", count(CheckStatus::Error));
+    }
+
+    Ok(())
+}
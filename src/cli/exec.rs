@@ -0,0 +1,63 @@
+use std::ffi::OsString;
+
+use anyhow::Result;
+use git2::Repository;
+use glob::Pattern;
+
+use crate::utils::{
+    cli::select_worktrees,
+    exec::{exec_in_worktrees, filter_worktrees, WorktreeFilter},
+    git::{alias::resolve_worktree_alias, worktree::get_worktrees},
+};
+
+/// Returns the exit code to exit with, the highest one of all failed commands
+pub(crate) async fn exec_sub_command(
+    repo: Repository,
+    command: Vec<OsString>,
+    select: bool,
+    parallel: usize,
+    worktree_names: Vec<String>,
+    branch: Option<String>,
+    dirty: Option<bool>,
+) -> Result<i32> {
+    let filter = WorktreeFilter {
+        names: worktree_names
+            .iter()
+            .map(|worktree_name| resolve_worktree_alias(&repo, worktree_name))
+            .collect(),
+        branch: branch.as_deref().map(Pattern::new).transpose()?,
+        dirty,
+    };
+
+    let mut worktrees = filter_worktrees(&repo, get_worktrees(&repo), &filter);
+
+    if select {
+        let worktree_names = select_worktrees(&worktrees, None).await?;
+        worktrees.retain(|worktree| worktree_names.contains(&worktree.name));
+    }
+
+    if worktrees.is_empty() {
+        warn!("No worktree matches the given filters");
+        return Ok(0);
+    }
+
+    let exit_codes = exec_in_worktrees(worktrees, command, parallel).await?;
+
+    let mut exit_code = 0;
+
+    for (worktree_name, result) in exit_codes {
+        match result {
+            Ok(0) => {}
+            Ok(code) => {
+                eprintln!("[{}] exited with {}", worktree_name, code);
+                exit_code = exit_code.max(code);
+            }
+            Err(e) => {
+                eprintln!("[{}] {:#}", worktree_name, e);
+                exit_code = exit_code.max(1);
+            }
+        }
+    }
+
+    Ok(exit_code)
+}
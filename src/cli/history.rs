@@ -0,0 +1,56 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::{
+    cli::table::print_table,
+    history::{now, History},
+};
+
+fn format_age(seconds: i64) -> String {
+    match seconds {
+        seconds if seconds < 60 => String::from("just now"),
+        seconds if seconds < 60 * 60 => format!("{}m ago", seconds / 60),
+        seconds if seconds < 24 * 60 * 60 => format!("{}h ago", seconds / (60 * 60)),
+        seconds => format!("{}d ago", seconds / (24 * 60 * 60)),
+    }
+}
+
+pub(crate) fn history_sub_command(repo: Repository, limit: usize, clear: bool) -> Result<()> {
+    let mut history = History::load(&repo);
+
+    if clear {
+        history.clear();
+        history.save(&repo)?;
+
+        println!("Cleared the switch history");
+
+        return Ok(());
+    }
+
+    let summaries = history.summaries();
+
+    if summaries.is_empty() {
+        println!("No switches recorded yet");
+
+        return Ok(());
+    }
+
+    let now = now();
+
+    let rows = summaries
+        .iter()
+        .take(limit)
+        .map(|summary| {
+            [
+                summary.name.clone(),
+                format_age(now - summary.last_switch),
+                summary.switches.to_string(),
+                format!("{:.2}", summary.score),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_table(["NAME", "LAST SWITCH", "SWITCHES", "SCORE"], &rows);
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::git::worktree::{lock_worktree, normalize_workspace_name, unlock_worktree};
+
+pub(crate) fn lock_sub_command(
+    repo: Repository,
+    worktree_name: String,
+    reason: Option<String>,
+) -> Result<()> {
+    let worktree_name = normalize_workspace_name(&worktree_name);
+
+    lock_worktree(&repo, &worktree_name, reason.as_deref())?;
+
+    info!("Locked worktree `{}`", worktree_name);
+
+    Ok(())
+}
+
+pub(crate) fn unlock_sub_command(repo: Repository, worktree_name: String) -> Result<()> {
+    let worktree_name = normalize_workspace_name(&worktree_name);
+
+    unlock_worktree(&repo, &worktree_name)?;
+
+    info!("Unlocked worktree `{}`", worktree_name);
+
+    Ok(())
+}
@@ -1,3 +1,19 @@
 pub(crate) mod add;
+pub(crate) mod alias;
+pub(crate) mod auth;
 pub(crate) mod change_branch;
 pub(crate) mod completions;
+pub(crate) mod doctor;
+pub(crate) mod exec;
+pub(crate) mod history;
+pub(crate) mod lock;
+pub(crate) mod mv;
+pub(crate) mod prune;
+pub(crate) mod rebase_all;
+pub(crate) mod remove;
+pub(crate) mod repair;
+pub(crate) mod repo;
+pub(crate) mod status;
+pub(crate) mod switch;
+pub(crate) mod sync;
+pub(crate) mod tui;
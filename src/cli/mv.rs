@@ -0,0 +1,17 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::git::worktree::move_worktree;
+
+pub(crate) fn mv_sub_command(
+    repo: Repository,
+    old_name: String,
+    new_name: String,
+    force: bool,
+) -> Result<()> {
+    let worktree_path = move_worktree(&repo, &old_name, &new_name, force)?;
+
+    println!("cd {}", worktree_path.display());
+
+    Ok(())
+}
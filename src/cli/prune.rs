@@ -0,0 +1,25 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::git::worktree::prune_worktrees;
+
+pub(crate) fn prune_sub_command(repo: Repository, force: bool) -> Result<()> {
+    let (pruned, kept) = prune_worktrees(&repo, force)?;
+
+    for worktree_name in &pruned {
+        println!("Pruned `{}`", worktree_name);
+    }
+
+    for worktree_name in &kept {
+        println!(
+            "Kept `{}` because it is locked, use --force to prune it",
+            worktree_name
+        );
+    }
+
+    if pruned.is_empty() && kept.is_empty() {
+        println!("No worktrees to prune");
+    }
+
+    Ok(())
+}
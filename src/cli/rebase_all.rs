@@ -0,0 +1,45 @@
+use anyhow::{bail, Result};
+use git2::Repository;
+
+use crate::utils::{
+    cli::select_worktrees,
+    git::{
+        fetch::FetchSettings, rebase::rebase_worktrees, sync::SyncStatus, worktree::get_worktrees,
+    },
+};
+
+use super::sync::print_sync_results;
+
+pub(crate) async fn rebase_all_sub_command(
+    repo: Repository,
+    onto: String,
+    merge: bool,
+    select: bool,
+    fetch_settings: FetchSettings,
+) -> Result<()> {
+    let worktrees = get_worktrees(&repo);
+
+    let worktree_names = if select {
+        select_worktrees(&worktrees, None).await?
+    } else {
+        worktrees
+            .into_iter()
+            .map(|worktree| worktree.name)
+            .collect()
+    };
+
+    let rebase_results = rebase_worktrees(&repo, worktree_names, &onto, merge, &fetch_settings)?;
+
+    print_sync_results(&rebase_results);
+
+    let failed = rebase_results
+        .iter()
+        .filter(|rebase_result| rebase_result.status == SyncStatus::Failed)
+        .count();
+
+    if failed > 0 {
+        bail!("Failed to update {} worktree(s)", failed);
+    }
+
+    Ok(())
+}
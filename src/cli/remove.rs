@@ -0,0 +1,21 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::git::{
+    alias::resolve_worktree_alias,
+    worktree::{normalize_workspace_name, remove_worktree},
+};
+
+pub(crate) fn remove_sub_command(
+    repo: Repository,
+    worktree_name: String,
+    force: bool,
+) -> Result<()> {
+    let worktree_name = normalize_workspace_name(&resolve_worktree_alias(&repo, &worktree_name));
+
+    remove_worktree(&repo, &worktree_name, force)?;
+
+    info!("Removed worktree `{}`", worktree_name);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use git2::Repository;
+
+use crate::utils::{
+    cli::table::print_table,
+    git::repair::{repair_worktrees, RepairSettings, RepairStatus},
+};
+
+pub(crate) fn repair_sub_command(
+    repo: Repository,
+    search_paths: Vec<PathBuf>,
+    prune: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let repair_results = repair_worktrees(
+        &repo,
+        RepairSettings {
+            search_paths,
+            prune,
+            dry_run,
+            ..Default::default()
+        },
+    )?;
+
+    let rows = repair_results
+        .iter()
+        .map(|repair_result| {
+            [
+                repair_result.worktree.clone(),
+                repair_result.status.to_string(),
+                repair_result
+                    .path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default(),
+                repair_result.detail.clone(),
+            ]
+        })
+        .collect::<Vec<[String; 4]>>();
+
+    print_table(["WORKTREE", "STATUS", "PATH", "DETAILS"], &rows);
+
+    let count = |status: RepairStatus| {
+        repair_results
+            .iter()
+            .filter(|repair_result| repair_result.status == status)
+            .count()
+    };
+
+    println!(
+        "\n{} ok, {} repaired, {} need repair, {} missing, {} pruned, {} conflicted, {} failed",
+        count(RepairStatus::Ok),
+        count(RepairStatus::Repaired),
+        count(RepairStatus::NeedsRepair),
+        count(RepairStatus::Missing),
+        count(RepairStatus::Pruned),
+        count(RepairStatus::Conflict),
+        count(RepairStatus::Failed)
+    );
+
+    if count(RepairStatus::Failed) > 0 {
+        bail!(
+            "Failed to repair {} worktree(s)",
+            count(RepairStatus::Failed)
+        );
+    }
+
+    Ok(())
+}
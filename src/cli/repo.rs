@@ -0,0 +1,74 @@
+use std::ffi::OsString;
+
+use anyhow::{Context, Result};
+use git2::Repository;
+
+use crate::utils::{
+    cli::table::print_table,
+    git::{common::get_common_dir, worktree::get_worktree_names},
+    registry::{get_repository_name, Registry},
+};
+
+pub(crate) fn repo_add_sub_command(repo_path: OsString) -> Result<()> {
+    let repo = Repository::open(&repo_path)
+        .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+    let mut registry = Registry::load()?;
+    let common_dir = get_common_dir(&repo);
+
+    if registry.add(&repo) {
+        registry.save()?;
+
+        println!("Registered `{}`", get_repository_name(&common_dir));
+    } else {
+        println!(
+            "`{}` is already registered",
+            get_repository_name(&common_dir)
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) fn repo_list_sub_command() -> Result<()> {
+    let registry = Registry::load()?;
+
+    if registry.repositories().is_empty() {
+        println!("No repositories registered");
+
+        return Ok(());
+    }
+
+    let rows = registry
+        .repositories()
+        .iter()
+        .map(|common_dir| {
+            let worktrees = match Repository::open(common_dir) {
+                Ok(repo) => get_worktree_names(&repo).len().to_string(),
+                Err(_) => String::from("missing"),
+            };
+
+            [
+                get_repository_name(common_dir),
+                worktrees,
+                common_dir.display().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_table(["NAME", "WORKTREES", "PATH"], &rows);
+
+    Ok(())
+}
+
+pub(crate) fn repo_remove_sub_command(repository: String) -> Result<()> {
+    let mut registry = Registry::load()?;
+
+    let common_dir = registry.remove(&repository)?;
+
+    registry.save()?;
+
+    println!("Unregistered `{}`", get_repository_name(&common_dir));
+
+    Ok(())
+}
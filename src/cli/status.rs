@@ -0,0 +1,92 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::{
+    utils::{
+        cli::table::print_table,
+        git::status::{
+            format_ahead_behind, format_count, get_default_base_branch, get_worktree_statuses,
+        },
+    },
+    OutputFormat,
+};
+
+pub(crate) fn status_sub_command(
+    repo: Repository,
+    base: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let base = base.or_else(|| get_default_base_branch(&repo));
+    let statuses = get_worktree_statuses(&repo, base.as_deref());
+
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    let rows = statuses
+        .iter()
+        .map(|status| {
+            // The counts of a worktree whose status failed are unknown, not zero
+            let known = |value: String| {
+                if status.error.is_some() {
+                    String::from("?")
+                } else {
+                    value
+                }
+            };
+
+            [
+                status.name.clone(),
+                status.head_label(),
+                known(format_count(status.staged)),
+                known(format_count(status.unstaged)),
+                known(format_count(status.untracked)),
+                known(format_count(status.conflicted)),
+                known(format_count(status.stashes)),
+                known(format_ahead_behind(&status.upstream)),
+                known(format_ahead_behind(&status.base)),
+                known(status.operation.unwrap_or("-").to_string()),
+                match &status.locked {
+                    Some(reason) if !reason.is_empty() => format!("yes ({})", reason),
+                    Some(_) => String::from("yes"),
+                    None => String::from("-"),
+                },
+            ]
+        })
+        .collect::<Vec<[String; 11]>>();
+
+    let base_header = format!("BASE ({})", base.as_deref().unwrap_or("none"));
+
+    print_table(
+        [
+            "WORKTREE",
+            "BRANCH",
+            "STAGED",
+            "UNSTAGED",
+            "UNTRACKED",
+            "CONFLICTS",
+            "STASHES",
+            "UPSTREAM",
+            &base_header,
+            "IN PROGRESS",
+            "LOCKED",
+        ],
+        &rows,
+    );
+
+    let errors = statuses
+        .iter()
+        .filter_map(|status| Some((&status.name, status.error.as_ref()?)))
+        .collect::<Vec<_>>();
+
+    if !errors.is_empty() {
+        println!();
+
+        for (name, error) in errors {
+            println!("? {}: {}", name, error);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::ffi::OsString;
+
+use anyhow::Result;
+
+use crate::utils::cli::switch_across_repositories;
+
+pub(crate) async fn switch_sub_command(query: Option<OsString>) -> Result<()> {
+    let query = query.map(|os_str| os_str.to_string_lossy().into_owned());
+
+    println!("{}", switch_across_repositories(query).await?);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use anyhow::{bail, Result};
+use git2::Repository;
+
+use crate::utils::{
+    cli::table::print_table,
+    git::{
+        fetch::FetchSettings,
+        sync::{sync_worktrees, SyncResult, SyncStatus},
+    },
+};
+
+pub(crate) fn sync_sub_command(
+    repo: Repository,
+    rebase: bool,
+    fetch_settings: FetchSettings,
+) -> Result<()> {
+    let sync_results = sync_worktrees(&repo, rebase, &fetch_settings)?;
+
+    print_sync_results(&sync_results);
+
+    let failed = sync_results
+        .iter()
+        .filter(|sync_result| sync_result.status == SyncStatus::Failed)
+        .count();
+
+    if failed > 0 {
+        bail!("Failed to sync {} worktree(s)", failed);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn print_sync_results(sync_results: &[SyncResult]) {
+    let rows = sync_results
+        .iter()
+        .map(|sync_result| {
+            [
+                sync_result.worktree.clone(),
+                sync_result.status.to_string(),
+                sync_result.branch.clone().unwrap_or_default(),
+                sync_result.detail.clone(),
+            ]
+        })
+        .collect::<Vec<[String; 4]>>();
+
+    print_table(["WORKTREE", "STATUS", "BRANCH", "DETAILS"], &rows);
+
+    let count = |status: SyncStatus| {
+        sync_results
+            .iter()
+            .filter(|sync_result| sync_result.status == status)
+            .count()
+    };
+
+    println!(
+        "\n{} updated, {} up to date, {} skipped, {} diverged, {} conflicted, {} failed",
+        count(SyncStatus::Updated),
+        count(SyncStatus::UpToDate),
+        count(SyncStatus::Skipped),
+        count(SyncStatus::Diverged),
+        count(SyncStatus::Conflicted),
+        count(SyncStatus::Failed)
+    );
+}
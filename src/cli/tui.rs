@@ -0,0 +1,14 @@
+use anyhow::Result;
+use git2::Repository;
+
+use crate::utils::{git::status::get_default_base_branch, tui::run_tui};
+
+pub(crate) async fn tui_sub_command(repo: Repository, base: Option<String>) -> Result<()> {
+    let base = base.or_else(|| get_default_base_branch(&repo));
+
+    if let Some(command) = run_tui(&repo, base).await? {
+        println!("{}", command);
+    }
+
+    Ok(())
+}
@@ -1,14 +1,39 @@
-use std::{ffi::OsString, fs};
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 use cli::{
     add::{add_from_pr_sub_command, add_sub_command},
+    alias::{alias_add_sub_command, alias_list_sub_command, alias_remove_sub_command},
+    auth::auth_status_sub_command,
     change_branch::change_branch_sub_command,
     completions::completions_sub_command,
+    doctor::doctor_sub_command,
+    exec::exec_sub_command,
+    history::history_sub_command,
+    lock::{lock_sub_command, unlock_sub_command},
+    mv::mv_sub_command,
+    prune::prune_sub_command,
+    rebase_all::rebase_all_sub_command,
+    remove::remove_sub_command,
+    repair::repair_sub_command,
+    repo::{repo_add_sub_command, repo_list_sub_command, repo_remove_sub_command},
+    status::status_sub_command,
+    switch::switch_sub_command,
+    sync::sync_sub_command,
+    tui::tui_sub_command,
+};
+use git2::Repository;
+use utils::{
+    forge::ChangeRequestState,
+    git::{fetch::FetchSettings, open_repo},
+    registry::register_repository,
+    signal::install_ctrl_c_handler,
 };
-use octocrab::params::State;
-use utils::git::open_repo;
 
 extern crate pretty_env_logger;
 #[macro_use]
@@ -41,6 +66,126 @@ pub(crate) enum PrSelection {
     Single,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub(crate) enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Args)]
+struct FetchArgs {
+    #[clap(long, help = "Do not report fetch progress")]
+    quiet: bool,
+    #[clap(long, help = "Skip fetching from the remote, e.g. when offline")]
+    no_fetch: bool,
+    #[clap(
+        long,
+        help = "Fetch everything the remote is configured to fetch, not only the needed refs",
+        conflicts_with = "no_fetch"
+    )]
+    full_fetch: bool,
+    #[clap(
+        long,
+        help = "Limit fetching to the given number of commits",
+        value_name = "DEPTH",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with = "no_fetch"
+    )]
+    depth: Option<u32>,
+}
+
+impl From<FetchArgs> for FetchSettings {
+    fn from(args: FetchArgs) -> Self {
+        FetchSettings {
+            quiet: args.quiet,
+            skip: args.no_fetch,
+            full: args.full_fetch,
+            depth: args.depth,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum AuthSubCommands {
+    #[command(about = "Show which GitHub token is used, its scopes and rate limit")]
+    Status {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to a git repository, its GitHub host is used instead of github.com",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AliasSubCommands {
+    #[command(
+        arg_required_else_help = true,
+        about = "Add a short alias for a worktree"
+    )]
+    Add {
+        #[arg(help = "Alias to add", value_name = "ALIAS")]
+        alias: String,
+        #[arg(help = "Name of the worktree the alias points to", value_name = "NAME")]
+        worktree_name: String,
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+    #[command(about = "List the aliases of worktrees")]
+    List {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+    #[command(arg_required_else_help = true, about = "Remove an alias")]
+    Remove {
+        #[arg(help = "Alias to remove", value_name = "ALIAS")]
+        alias: String,
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum RepoSubCommands {
+    #[command(about = "Register a repository, repositories are also registered on use")]
+    Add {
+        #[arg(
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+    #[command(about = "List the registered repositories")]
+    List,
+    #[command(arg_required_else_help = true, about = "Unregister a repository")]
+    Remove {
+        #[arg(help = "Path or name of the repository", value_name = "REPOSITORY")]
+        repository: String,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 #[allow(clippy::large_enum_variant)]
 enum SubCommands {
@@ -51,11 +196,11 @@ enum SubCommands {
     Add {
         #[arg(
             value_enum,
-            help = "Name of the worktree/branch to add",
-            required = true,
+            help = "Name of the worktree/branch to add, defaults to the revision with --detach",
+            required_unless_present = "detach",
             value_name = "name"
         )]
-        name: OsString,
+        name: Option<OsString>,
         #[clap(
             short = 'p',
             long,
@@ -64,9 +209,18 @@ enum SubCommands {
             value_hint = clap::ValueHint::DirPath
         )]
         repo_path: OsString,
+        #[clap(
+            short,
+            long,
+            help = "Add the worktree with a detached HEAD at a tag or commit",
+            value_name = "REV"
+        )]
+        detach: Option<String>,
+        #[command(flatten)]
+        fetch: FetchArgs,
     },
 
-    #[command(about = "Add a new worktree/branch to a git repository from a PR")]
+    #[command(about = "Add a new worktree/branch to a git repository from a PR or MR")]
     AddByPR {
         #[clap(
             short = 'p',
@@ -94,10 +248,44 @@ enum SubCommands {
             default_value = "multiple"
         )]
         pr_selection: PrSelection,
+        #[clap(
+            short = 'n',
+            long = "number",
+            help = "Number of the PR/MR to add, skips the selection (can be repeated)",
+            value_name = "NUMBER"
+        )]
+        pr_numbers: Vec<u64>,
+        #[clap(
+            short = 'o',
+            long,
+            value_enum,
+            help = "Format of the per-PR results",
+            value_name = "FORMAT",
+            default_value = "table"
+        )]
+        output: OutputFormat,
+        #[clap(
+            short = 'j',
+            long,
+            help = "Number of worktrees to create at once",
+            value_name = "JOBS",
+            value_parser = clap::value_parser!(u16).range(1..),
+            default_value = "4"
+        )]
+        jobs: u16,
+        #[command(flatten)]
+        fetch: FetchArgs,
     },
 
     #[command(about = "Change branch or worktree of a git repository")]
     ChangeBranch {
+        #[arg(
+            help = "Pass `-` to switch to the previous worktree",
+            value_name = "PREVIOUS",
+            value_parser = ["-"],
+            conflicts_with_all = ["branch", "worktree", "query"]
+        )]
+        previous: Option<String>,
         #[clap(
             short = 'p',
             long,
@@ -108,10 +296,331 @@ enum SubCommands {
         repo_path: OsString,
         #[clap(short, long, help = "Branch name to change to")]
         branch: Option<OsString>,
-        #[clap(short, long, help = "Worktree name to change to")]
+        #[clap(short, long, help = "Worktree name or alias to change to")]
         worktree: Option<OsString>,
         #[clap(short, long, help = "Query string to filter results")]
         query: Option<OsString>,
+        #[clap(
+            long,
+            help = "Check out the branch instead of printing the command, in a repository without worktrees"
+        )]
+        checkout: bool,
+        #[clap(
+            long,
+            help = "Stash uncommitted changes and re-apply them after checking out, implies --checkout"
+        )]
+        autostash: bool,
+        #[command(flatten)]
+        fetch: FetchArgs,
+    },
+    #[command(about = "Fetch and fast-forward all worktrees to their upstream")]
+    Sync {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(long, help = "Rebase worktrees which diverged from their upstream")]
+        rebase: bool,
+        #[command(flatten)]
+        fetch: FetchArgs,
+    },
+    #[command(about = "Rebase or merge all worktrees onto a base branch")]
+    RebaseAll {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(
+            long,
+            help = "Branch or revision to rebase onto, e.g. `main` or `origin/main`",
+            value_name = "BASE"
+        )]
+        onto: String,
+        #[clap(long, help = "Merge the base branch instead of rebasing")]
+        merge: bool,
+        #[clap(short, long, help = "Select the worktrees to update")]
+        select: bool,
+        #[command(flatten)]
+        fetch: FetchArgs,
+    },
+    #[command(
+        arg_required_else_help = true,
+        about = "Run a command in every worktree, e.g. `exec -- cargo check`"
+    )]
+    Exec {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(short, long, help = "Select the worktrees to run the command in")]
+        select: bool,
+        #[clap(
+            short = 'j',
+            long,
+            help = "Number of commands to run at once",
+            value_name = "N",
+            value_parser = clap::value_parser!(u16).range(1..),
+            default_value = "1"
+        )]
+        parallel: u16,
+        #[clap(
+            short,
+            long = "worktree",
+            help = "Only the given worktrees, by name or alias, can be repeated",
+            value_name = "NAME"
+        )]
+        worktrees: Vec<String>,
+        #[clap(
+            short,
+            long,
+            help = "Only worktrees with a branch matching the glob, e.g. `feature/*`",
+            value_name = "GLOB"
+        )]
+        branch: Option<String>,
+        #[clap(long, help = "Only worktrees with uncommitted changes")]
+        dirty: bool,
+        #[clap(
+            long,
+            help = "Only worktrees without uncommitted changes",
+            conflicts_with = "dirty"
+        )]
+        clean: bool,
+        #[arg(
+            last = true,
+            required = true,
+            help = "Command to run",
+            value_name = "COMMAND"
+        )]
+        command: Vec<OsString>,
+    },
+    #[command(about = "Show uncommitted work and divergence of all worktrees")]
+    Status {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(
+            long,
+            help = "Branch to compare with, defaults to `origin/HEAD`, `main` or `master`",
+            value_name = "BASE"
+        )]
+        base: Option<String>,
+        #[clap(
+            short = 'o',
+            long,
+            value_enum,
+            help = "Output format",
+            value_name = "FORMAT",
+            default_value = "table"
+        )]
+        output: OutputFormat,
+    },
+    #[command(about = "Manage worktrees in an interactive terminal UI")]
+    Tui {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(
+            long,
+            help = "Branch to compare with, defaults to `origin/HEAD`, `main` or `master`",
+            value_name = "BASE"
+        )]
+        base: Option<String>,
+    },
+    #[command(
+        arg_required_else_help = true,
+        about = "Rename a worktree together with its branch"
+    )]
+    Mv {
+        #[arg(help = "Name of the worktree to rename", value_name = "OLD")]
+        old_name: String,
+        #[arg(help = "New name of the branch and worktree", value_name = "NEW")]
+        new_name: String,
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(short, long, help = "Move the worktree even if it is locked")]
+        force: bool,
+    },
+    #[command(
+        arg_required_else_help = true,
+        about = "Remove a worktree together with its directory"
+    )]
+    Remove {
+        #[arg(help = "Name or alias of the worktree to remove", value_name = "NAME")]
+        worktree_name: String,
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(
+            short,
+            long,
+            help = "Remove the worktree even if it is locked or has uncommitted changes"
+        )]
+        force: bool,
+    },
+    #[command(about = "Show recent switches between worktrees, ranked by frecency in pickers")]
+    History {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(
+            short = 'n',
+            long,
+            help = "Number of entries to show",
+            default_value_t = 10
+        )]
+        limit: usize,
+        #[clap(long, help = "Forget all recorded switches")]
+        clear: bool,
+    },
+    #[command(about = "Remove the metadata of worktrees whose directory was deleted")]
+    Prune {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(short, long, help = "Also prune locked worktrees")]
+        force: bool,
+    },
+    #[command(
+        arg_required_else_help = true,
+        about = "Lock a worktree so it cannot be removed, pruned or moved"
+    )]
+    Lock {
+        #[arg(help = "Name of the worktree to lock", value_name = "NAME")]
+        worktree_name: String,
+        #[clap(long, help = "Why the worktree is locked")]
+        reason: Option<String>,
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+    #[command(arg_required_else_help = true, about = "Unlock a locked worktree")]
+    Unlock {
+        #[arg(help = "Name of the worktree to unlock", value_name = "NAME")]
+        worktree_name: String,
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+    },
+    #[command(about = "Detect and repair worktrees which were moved or deleted")]
+    Repair {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(
+            short = 's',
+            long = "search-path",
+            help = "Directory to look for moved worktrees in, defaults to the directory of the repository",
+            value_name = "DIR",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        search_paths: Vec<PathBuf>,
+        #[clap(long, help = "Remove the metadata of worktrees which cannot be found")]
+        prune: bool,
+        #[clap(
+            short = 'n',
+            long,
+            help = "Only report problems without repairing them"
+        )]
+        dry_run: bool,
+    },
+    #[command(about = "Check the repository setup and suggest fixes for the problems found")]
+    Doctor {
+        #[clap(
+            short = 'p',
+            long,
+            help = "Path to the git repository",
+            default_value = ".",
+            value_hint = clap::ValueHint::DirPath
+        )]
+        repo_path: OsString,
+        #[clap(long, help = "Apply the fixes which cannot lose any data")]
+        fix: bool,
+        #[clap(long, help = "Skip the checks which need network access")]
+        offline: bool,
+    },
+    #[command(
+        arg_required_else_help = true,
+        about = "Manage short aliases for worktrees"
+    )]
+    Alias {
+        #[clap(subcommand)]
+        subcommands: AliasSubCommands,
+    },
+    #[command(
+        arg_required_else_help = true,
+        about = "Manage the registry of known repositories"
+    )]
+    Repo {
+        #[clap(subcommand)]
+        subcommands: RepoSubCommands,
+    },
+    #[command(about = "Change to a worktree of any registered repository")]
+    Switch {
+        #[clap(short, long, help = "Query string to filter results")]
+        query: Option<OsString>,
+    },
+    #[command(arg_required_else_help = true, about = "Manage GitHub authentication")]
+    Auth {
+        #[clap(subcommand)]
+        subcommands: AuthSubCommands,
     },
     #[command(arg_required_else_help = true, about = "Generate shell completions")]
     Completions {
@@ -133,18 +642,33 @@ pub struct CLI {
     subcommands: SubCommands,
 }
 
+/// Opens the repository of a subcommand and registers it, so `switch` knows about it
+fn open_registered_repo(repo_path: &Path) -> Repository {
+    let repo = open_repo(&repo_path);
+
+    register_repository(&repo);
+
+    repo
+}
+
 #[tokio::main]
 async fn main() {
     pretty_env_logger::init();
+    install_ctrl_c_handler();
 
     let opt = CLI::parse();
 
     match opt.subcommands {
-        SubCommands::Add { name, repo_path } => {
+        SubCommands::Add {
+            name,
+            repo_path,
+            detach,
+            fetch,
+        } => {
             let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
-            let repo = open_repo(&repo_path);
+            let repo = open_registered_repo(&repo_path);
 
-            match add_sub_command(repo, name) {
+            match add_sub_command(repo, name, detach, fetch.into()) {
                 Ok(_) => {
                     info!("Worktree/branch was added successfully");
                 }
@@ -157,34 +681,310 @@ async fn main() {
             repo_path,
             pr_kind,
             pr_selection,
+            pr_numbers,
+            output,
+            jobs,
+            fetch,
         } => {
             let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
-            let repo = open_repo(&repo_path);
+            let repo = open_registered_repo(&repo_path);
 
-            match add_from_pr_sub_command(repo, State::Open, pr_kind, pr_selection).await {
+            match add_from_pr_sub_command(
+                repo,
+                ChangeRequestState::Open,
+                pr_kind,
+                pr_selection,
+                pr_numbers,
+                fetch.into(),
+                output,
+                jobs.into(),
+            )
+            .await
+            {
                 Ok(_) => {
                     info!("All PRs were added successfully");
                 }
                 Err(e) => {
-                    error!("Failed to get PR: {:?}", e);
+                    error!("Failed to add PRs: {:?}", e);
+                    std::process::exit(exitcode::SOFTWARE);
                 }
             }
         }
+        SubCommands::Sync {
+            repo_path,
+            rebase,
+            fetch,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = sync_sub_command(repo, rebase, fetch.into()) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::RebaseAll {
+            repo_path,
+            onto,
+            merge,
+            select,
+            fetch,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = rebase_all_sub_command(repo, onto, merge, select, fetch.into()).await {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Exec {
+            repo_path,
+            select,
+            parallel,
+            worktrees,
+            branch,
+            dirty,
+            clean,
+            command,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            let dirty = (dirty || clean).then_some(dirty);
+
+            match exec_sub_command(
+                repo,
+                command,
+                select,
+                parallel.into(),
+                worktrees,
+                branch,
+                dirty,
+            )
+            .await
+            {
+                Ok(exit_code) => std::process::exit(exit_code),
+                Err(e) => {
+                    error!("{:?}", e);
+                    std::process::exit(exitcode::SOFTWARE);
+                }
+            }
+        }
+        SubCommands::Status {
+            repo_path,
+            base,
+            output,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = status_sub_command(repo, base, output) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Tui { repo_path, base } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = tui_sub_command(repo, base).await {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Mv {
+            old_name,
+            new_name,
+            repo_path,
+            force,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = mv_sub_command(repo, old_name, new_name, force) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Remove {
+            worktree_name,
+            repo_path,
+            force,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = remove_sub_command(repo, worktree_name, force) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::History {
+            repo_path,
+            limit,
+            clear,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = history_sub_command(repo, limit, clear) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Prune { repo_path, force } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = prune_sub_command(repo, force) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Lock {
+            worktree_name,
+            reason,
+            repo_path,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = lock_sub_command(repo, worktree_name, reason) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Unlock {
+            worktree_name,
+            repo_path,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = unlock_sub_command(repo, worktree_name) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Repair {
+            repo_path,
+            search_paths,
+            prune,
+            dry_run,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = repair_sub_command(repo, search_paths, prune, dry_run) {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Doctor {
+            repo_path,
+            fix,
+            offline,
+        } => {
+            let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
+            let repo = open_registered_repo(&repo_path);
+
+            if let Err(e) = doctor_sub_command(repo, fix, offline).await {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Alias { subcommands } => {
+            let result = match subcommands {
+                AliasSubCommands::Add {
+                    alias,
+                    worktree_name,
+                    repo_path,
+                } => {
+                    let repo_path =
+                        fs::canonicalize(repo_path).expect("Failed to get worktree path");
+
+                    alias_add_sub_command(open_registered_repo(&repo_path), alias, worktree_name)
+                }
+                AliasSubCommands::List { repo_path } => {
+                    let repo_path =
+                        fs::canonicalize(repo_path).expect("Failed to get worktree path");
+
+                    alias_list_sub_command(open_registered_repo(&repo_path))
+                }
+                AliasSubCommands::Remove { alias, repo_path } => {
+                    let repo_path =
+                        fs::canonicalize(repo_path).expect("Failed to get worktree path");
+
+                    alias_remove_sub_command(open_registered_repo(&repo_path), alias)
+                }
+            };
+
+            if let Err(e) = result {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Repo { subcommands } => {
+            let result = match subcommands {
+                RepoSubCommands::Add { repo_path } => repo_add_sub_command(repo_path),
+                RepoSubCommands::List => repo_list_sub_command(),
+                RepoSubCommands::Remove { repository } => repo_remove_sub_command(repository),
+            };
+
+            if let Err(e) = result {
+                error!("{:?}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Switch { query } => {
+            if let Err(e) = switch_sub_command(query).await {
+                error!("{}", e);
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        }
+        SubCommands::Auth { subcommands } => match subcommands {
+            AuthSubCommands::Status { repo_path } => {
+                // Works outside of a repository too, for github.com
+                let repo = Repository::discover(repo_path).ok();
+
+                if let Err(e) = auth_status_sub_command(repo).await {
+                    error!("{}", e);
+                    std::process::exit(exitcode::SOFTWARE);
+                }
+            }
+        },
         SubCommands::Completions { shell } => {
             completions_sub_command(shell);
         }
         SubCommands::ChangeBranch {
+            previous,
             repo_path,
             branch,
             worktree,
             query,
+            checkout,
+            autostash,
+            fetch,
         } => {
             let repo_path = fs::canonicalize(repo_path).expect("Failed to get worktree path");
 
-            let repo = open_repo(&repo_path);
+            let repo = open_registered_repo(&repo_path);
 
-            if let Err(e) = change_branch_sub_command(repo, branch, worktree, query).await {
+            if let Err(e) = change_branch_sub_command(
+                repo,
+                branch,
+                worktree,
+                query,
+                previous.is_some(),
+                checkout,
+                autostash,
+                fetch.into(),
+            )
+            .await
+            {
                 error!("{}", e);
+                std::process::exit(exitcode::SOFTWARE);
             }
         }
     }
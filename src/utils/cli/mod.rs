@@ -1,40 +1,84 @@
-use std::ffi::OsStr;
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
-use git2::{BranchType, Error, Repository};
+use anyhow::{bail, Context, Result};
+use git2::{BranchType, Commit, Error, Repository};
 
 use crate::utils::git::{
-    branch::{branch_exists_by_name, get_branch, get_worktree_branches},
-    worktree::{get_worktree_by_branch_name, get_worktree_path_by_name, worktree_exists_by_name},
+    alias::resolve_worktree_alias,
+    branch::{branch_exists_by_name, get_branch, get_remote_only_branch_names},
+    common::get_common_dir,
+    worktree::{
+        get_worktree_by_branch_name, get_worktree_path_by_name, get_worktrees,
+        worktree_exists_by_branch_name, worktree_exists_by_name, WorktreeInfo,
+    },
 };
 
 use super::{
     git::{
-        branch::{add_branch, get_branches, BranchInfo},
-        fetch::fetch_all,
+        branch::{add_branch, get_branches, get_local_branch_reference, BranchInfo},
+        checkout::checkout_branch,
+        fetch::{fetch_branches, FetchSettings},
         is_branch_clear,
-        worktree::{add_worktree, AddKind},
+        rebase::UpdateOutcome,
+        worktree::{add_detached_worktree, add_worktree, AddKind},
     },
+    history::{get_current_name, record_switch, History},
+    registry::{get_repository_name, Registry},
     search::common::{get_fuzzy_options, handle_final_key},
 };
 
+pub(crate) mod table;
+
+const REMOTE_MARKER: &str = " [remote]";
+
 pub(crate) async fn change_branch_of_bare_or_worktree_repo(
     repo: &Repository,
     branch_name_arg: &Option<String>,
     worktree_name_arg: &Option<String>,
     query: Option<String>,
+    previous: bool,
+    fetch_settings: &FetchSettings,
 ) -> Result<String> {
-    let worktree_name_from_args = if let Some(worktree_name) = worktree_name_arg {
+    let history = History::load(repo);
+
+    let worktree_name_from_args = if previous {
+        let previous_name = history
+            .previous(get_current_name(repo).as_deref(), |worktree_name| {
+                worktree_exists_by_name(repo, &worktree_name).unwrap_or(false)
+            })
+            .context("No previous worktree to switch to")?;
+
+        Some(previous_name)
+    } else if let Some(worktree_name) = worktree_name_arg {
+        let worktree_name = resolve_worktree_alias(repo, worktree_name);
+
         if worktree_exists_by_name(repo, &worktree_name).unwrap_or(false) {
-            Some(worktree_name.to_string())
+            Some(worktree_name)
         } else {
             None
         }
     } else if let Some(branch_name) = branch_name_arg {
         if branch_exists_by_name(repo, &branch_name, BranchType::Local).unwrap_or(false) {
-            Some(get_worktree_by_branch_name(repo, branch_name).unwrap())
+            // A local branch without a worktree gets one, like a remote branch does below
+            if !worktree_exists_by_branch_name(repo, branch_name)? {
+                let local_branch = get_branch(repo, branch_name, BranchType::Local);
+                add_worktree_from_branch(repo, branch_name, &local_branch)?;
+            }
+
+            Some(get_worktree_by_branch_name(repo, branch_name)?)
+        } else if get_branch(repo, branch_name, BranchType::Remote).is_some() {
+            add_worktree_to_repo(repo, branch_name, fetch_settings)?;
+
+            Some(get_worktree_by_branch_name(repo, branch_name)?)
         } else {
-            None
+            // Detached worktrees can be switched to by the tag they are at
+            get_worktrees(repo)
+                .into_iter()
+                .find(|worktree| worktree.tag.as_ref() == Some(branch_name))
+                .map(|worktree| worktree.name)
         }
     } else {
         None
@@ -45,18 +89,28 @@ pub(crate) async fn change_branch_of_bare_or_worktree_repo(
     } else {
         let branch_icon = "";
 
-        let worktree_branch_map =
-            get_worktree_branches(repo).expect("Failed to get worktree branches");
+        let mut worktrees = get_worktrees(repo);
+
+        history.sort_by_frecency(&mut worktrees, |worktree| &worktree.name);
+
+        let items = worktrees
+            .iter()
+            .map(|worktree| {
+                let head_label = worktree.head_label();
 
-        let items = worktree_branch_map
-            .into_iter()
-            .map(|(workspace, branch)| {
-                if workspace == branch {
-                    workspace
+                let item = if head_label == worktree.name {
+                    worktree.name.clone()
                 } else {
-                    format!("{} -> {}{}", workspace, branch_icon, branch)
-                }
+                    format!("{} -> {}{}", worktree.name, branch_icon, head_label)
+                };
+
+                add_markers(worktree, item)
             })
+            .chain(
+                get_remote_only_branch_names(repo)
+                    .into_iter()
+                    .map(|branch_name| format!("{}{}", branch_name, REMOTE_MARKER)),
+            )
             .collect::<Vec<String>>()
             .join("\n");
 
@@ -79,12 +133,14 @@ pub(crate) async fn change_branch_of_bare_or_worktree_repo(
 
             let selected_item = selected_items.first().expect("Workspace not selected");
 
-            let selected_branch_name = selected_item
-                .split(branch_icon)
-                .last()
-                .expect("Failed to get selected worktree name");
+            // Remote branches get a worktree before switching to it
+            if let Some(branch_name) = selected_item.strip_suffix(REMOTE_MARKER) {
+                add_worktree_to_repo(repo, branch_name, fetch_settings)?;
 
-            selected_branch_name.to_string()
+                get_worktree_by_branch_name(repo, branch_name)?
+            } else {
+                get_worktree_name_from_item(selected_item)
+            }
         } else {
             panic!("Workspace not selected")
         }
@@ -92,6 +148,8 @@ pub(crate) async fn change_branch_of_bare_or_worktree_repo(
 
     let worktree_path = get_worktree_path_by_name(repo, &worktree_name)?;
 
+    record_switch(repo, get_current_name(repo), &worktree_name);
+
     Ok(format!("cd {}", worktree_path))
 }
 
@@ -99,13 +157,102 @@ pub(crate) async fn change_branch_of_regular_repo(
     repo: &Repository,
     branch_name_arg: &Option<String>,
     query: Option<String>,
+    previous: bool,
+    fetch_settings: &FetchSettings,
 ) -> Result<String> {
     if !is_branch_clear(repo) {
         warn!("Branch has uncommitted changes");
         std::process::exit(exitcode::SOFTWARE);
     }
-    let branch_name_arg = if let Some(selected_branch_name) = branch_name_arg {
+
+    let branch_name =
+        select_local_branch(repo, branch_name_arg, query, previous, fetch_settings).await?;
+
+    record_switch(repo, get_current_name(repo), &branch_name);
+
+    Ok(format!("git checkout {}", branch_name))
+}
+
+/// Checks out the branch in-process instead of printing the command to do so
+pub(crate) async fn switch_branch_of_regular_repo(
+    repo: &mut Repository,
+    branch_name_arg: &Option<String>,
+    query: Option<String>,
+    autostash: bool,
+    previous: bool,
+    fetch_settings: &FetchSettings,
+) -> Result<()> {
+    let branch_name =
+        select_local_branch(repo, branch_name_arg, query, previous, fetch_settings).await?;
+
+    let current_name = get_current_name(repo);
+
+    let outcome = checkout_branch(repo, &branch_name, autostash)?;
+
+    record_switch(repo, current_name, &branch_name);
+
+    match outcome {
+        UpdateOutcome::Updated => {
+            // Stdout is evaluated by the shell
+            eprintln!("Switched to branch `{}`", branch_name);
+
+            Ok(())
+        }
+        UpdateOutcome::Conflicted(paths) if paths.is_empty() => bail!(
+            "Switched to branch `{}`, but the stashed changes could not be re-applied, they are kept in stash@{{0}}",
+            branch_name
+        ),
+        UpdateOutcome::Conflicted(paths) => bail!(
+            "Switched to branch `{}`, re-applying the stashed changes conflicts in {}. Resolve the conflicts and drop stash@{{0}}",
+            branch_name,
+            paths.join(", ")
+        ),
+    }
+}
+
+/// Creates a local branch tracking the branch of the same name on origin
+fn add_tracking_branch(
+    repo: &Repository,
+    branch_name: &str,
+    fetch_settings: &FetchSettings,
+) -> Result<()> {
+    fetch_branches(repo, &[branch_name], &[], fetch_settings)?;
+
+    let remote_branch = get_branch(repo, &branch_name, BranchType::Remote)
+        .with_context(|| format!("Branch `{}` not found on origin", branch_name))?;
+
+    add_branch_from_reference(repo, &remote_branch)?;
+
+    repo.find_branch(branch_name, BranchType::Local)?
+        .set_upstream(Some(&format!("origin/{}", branch_name)))?;
+
+    Ok(())
+}
+
+/// Lets the user pick a local or remote-only branch, a local branch is created for the latter
+async fn select_local_branch(
+    repo: &Repository,
+    branch_name_arg: &Option<String>,
+    query: Option<String>,
+    previous: bool,
+    fetch_settings: &FetchSettings,
+) -> Result<String> {
+    let history = History::load(repo);
+
+    let branch_name_arg = if previous {
+        let previous_name = history
+            .previous(get_current_name(repo).as_deref(), |branch_name| {
+                branch_exists_by_name(repo, &branch_name, BranchType::Local).unwrap_or(false)
+            })
+            .context("No previous branch to switch to")?;
+
+        Some(previous_name)
+    } else if let Some(selected_branch_name) = branch_name_arg {
         if branch_exists_by_name(repo, &selected_branch_name, BranchType::Local).unwrap_or(false) {
+            Some(selected_branch_name.to_string())
+        } else if get_branch(repo, selected_branch_name, BranchType::Remote).is_some() {
+            add_tracking_branch(repo, selected_branch_name, fetch_settings)?;
+
             Some(selected_branch_name.to_string())
         } else {
             None
@@ -117,11 +264,18 @@ pub(crate) async fn change_branch_of_regular_repo(
     let branch_name = if let Some(branch_name) = branch_name_arg {
         branch_name
     } else {
-        let local_branches: Vec<BranchInfo> = get_branches(repo, BranchType::Local);
+        let mut local_branches: Vec<BranchInfo> = get_branches(repo, BranchType::Local);
+
+        history.sort_by_frecency(&mut local_branches, |branch| &branch.name);
 
         let items = local_branches
             .iter()
             .map(|branch| branch.name.clone())
+            .chain(
+                get_remote_only_branch_names(repo)
+                    .into_iter()
+                    .map(|branch_name| format!("{}{}", branch_name, REMOTE_MARKER)),
+            )
             .collect::<Vec<String>>()
             .join("\n");
 
@@ -144,27 +298,112 @@ pub(crate) async fn change_branch_of_regular_repo(
 
             let selected_branch = selected_items.first().expect("Branch not selected");
 
-            selected_branch.to_string()
+            match selected_branch.strip_suffix(REMOTE_MARKER) {
+                Some(branch_name) => {
+                    add_tracking_branch(repo, branch_name, fetch_settings)?;
+
+                    branch_name.to_string()
+                }
+                None => selected_branch.to_string(),
+            }
         } else {
             panic!("Branch not selected");
         }
     };
 
-    Ok(format!("git checkout {}", branch_name))
+    Ok(branch_name)
 }
 
 pub(crate) fn add_worktree_to_repo<S>(
     repo: &Repository,
     worktree_name: S,
+    fetch_settings: &FetchSettings,
 ) -> Result<(String, AddKind)>
 where
     S: AsRef<OsStr>,
 {
-    fetch_all(repo);
+    fetch_branches(
+        repo,
+        &[worktree_name.as_ref().to_string_lossy()],
+        &[],
+        fetch_settings,
+    )?;
 
     let remote_branch = get_branch(repo, &worktree_name, BranchType::Remote);
 
-    let (worktree, add_kind) = add_worktree(repo, &worktree_name, &remote_branch)?;
+    add_worktree_from_branch(repo, worktree_name, &remote_branch)
+}
+
+/// Resolves `rev` to a commit, fetching it as a tag or branch when it is not known locally
+fn resolve_revision<'a>(
+    repo: &'a Repository,
+    rev: &str,
+    fetch_settings: &FetchSettings,
+) -> Result<Commit<'a>> {
+    if let Ok(commit) = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+    {
+        return Ok(commit);
+    }
+
+    fetch_branches(
+        repo,
+        &[rev],
+        &[format!("refs/tags/{}", rev)],
+        fetch_settings,
+    )?;
+
+    [rev.to_string(), format!("origin/{}", rev)]
+        .iter()
+        .find_map(|rev| {
+            repo.revparse_single(rev)
+                .and_then(|object| object.peel_to_commit())
+                .ok()
+        })
+        .with_context(|| format!("Revision `{}` not found", rev))
+}
+
+/// Adds a worktree with a detached HEAD at `rev`, named after `rev` unless a name is given
+pub(crate) fn add_detached_worktree_to_repo(
+    repo: &Repository,
+    worktree_name: Option<&str>,
+    rev: &str,
+    fetch_settings: &FetchSettings,
+) -> Result<(String, AddKind)> {
+    let commit = resolve_revision(repo, rev, fetch_settings)?;
+
+    let (worktree, add_kind) = add_detached_worktree(repo, worktree_name.unwrap_or(rev), &commit)?;
+
+    let worktree_path = worktree.path().to_string_lossy().to_string();
+
+    Ok((format!("cd {}", worktree_path), add_kind))
+}
+
+/// Detaching happens in place in a regular repository, `rev` is resolved first so a typo fails
+/// here instead of in the printed command
+pub(crate) fn checkout_detached_in_repo(
+    repo: &Repository,
+    rev: &str,
+    fetch_settings: &FetchSettings,
+) -> Result<(String, AddKind)> {
+    let commit = resolve_revision(repo, rev, fetch_settings)?;
+
+    Ok((
+        format!("git checkout --detach {}", commit.id()),
+        AddKind::Existed,
+    ))
+}
+
+pub(crate) fn add_worktree_from_branch<S>(
+    repo: &Repository,
+    worktree_name: S,
+    branch: &Option<BranchInfo>,
+) -> Result<(String, AddKind)>
+where
+    S: AsRef<OsStr>,
+{
+    let (worktree, add_kind) = add_worktree(repo, &worktree_name, branch)?;
 
     let worktree_path = worktree.path().to_string_lossy().to_string();
 
@@ -174,13 +413,274 @@ where
 pub(crate) fn add_branch_to_repo<S>(
     repo: &Repository,
     branch_name: S,
+    fetch_settings: &FetchSettings,
 ) -> Result<(String, AddKind), Error>
 where
     S: AsRef<OsStr>,
 {
-    fetch_all(repo);
+    fetch_branches(
+        repo,
+        &[branch_name.as_ref().to_string_lossy()],
+        &[],
+        fetch_settings,
+    )?;
 
     let (branch, add_kind) = add_branch(repo, &branch_name)?;
 
     Ok((format!("git checkout {}", branch.name), add_kind))
 }
+
+pub(crate) fn add_branch_from_reference(
+    repo: &Repository,
+    branch: &BranchInfo,
+) -> Result<(String, AddKind), Error> {
+    let add_kind = if branch_exists_by_name(repo, &branch.name, BranchType::Local)? {
+        AddKind::Existed
+    } else {
+        get_local_branch_reference(repo, branch)?;
+
+        AddKind::Added
+    };
+
+    Ok((format!("git checkout {}", branch.name), add_kind))
+}
+
+/// A worktree of a registered repository offered by `switch`
+struct SwitchTarget {
+    item: String,
+    path: PathBuf,
+    common_dir: PathBuf,
+    /// Name recorded in the history of the repository
+    name: String,
+    score: f64,
+}
+
+fn get_switch_targets(common_dir: &Path, repo_name: &str) -> Result<Vec<SwitchTarget>, Error> {
+    let repo = Repository::open(common_dir)?;
+    let history = History::load(&repo);
+
+    let mut targets = Vec::new();
+
+    if let (false, Some(workdir)) = (repo.is_bare(), repo.workdir()) {
+        let branch_name = get_current_name(&repo).unwrap_or_default();
+
+        targets.push(SwitchTarget {
+            item: format!("{} -> {}", repo_name, branch_name),
+            path: workdir.to_path_buf(),
+            common_dir: common_dir.to_path_buf(),
+            score: history.frecency(&branch_name),
+            name: branch_name,
+        });
+    }
+
+    for worktree in get_worktrees(&repo) {
+        let item = format!(
+            "{} / {} -> {}",
+            repo_name,
+            worktree.name,
+            worktree.head_label()
+        );
+
+        targets.push(SwitchTarget {
+            item: add_markers(&worktree, item),
+            path: worktree.path,
+            common_dir: common_dir.to_path_buf(),
+            score: history.frecency(&worktree.name),
+            name: worktree.name,
+        });
+    }
+
+    Ok(targets)
+}
+
+/// Lets the user pick a worktree of any registered repository
+pub(crate) async fn switch_across_repositories(query: Option<String>) -> Result<String> {
+    let registry = Registry::load()?;
+
+    if registry.repositories().is_empty() {
+        bail!("No repositories registered, add one with `repo add`");
+    }
+
+    let repo_names = registry
+        .repositories()
+        .iter()
+        .map(|common_dir| get_repository_name(common_dir))
+        .collect::<Vec<String>>();
+
+    let mut targets = Vec::new();
+
+    for (common_dir, repo_name) in registry.repositories().iter().zip(&repo_names) {
+        // Repositories sharing a name, e.g. `proj/.git` and `proj.git`, are told apart by path
+        let is_shared_name = repo_names.iter().filter(|name| *name == repo_name).count() > 1;
+        let repo_label = if is_shared_name {
+            common_dir.display().to_string()
+        } else {
+            repo_name.clone()
+        };
+
+        match get_switch_targets(common_dir, &repo_label) {
+            Ok(repo_targets) => targets.extend(repo_targets),
+            Err(e) => warn!(
+                "Skipping repository at {}: {}",
+                common_dir.display(),
+                e.message()
+            ),
+        }
+    }
+
+    // Stable sort, so targets without history stay grouped by repository
+    targets.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let items = targets
+        .iter()
+        .map(|target| target.item.clone())
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let out = get_fuzzy_options(query, false, String::from("Worktree"), items).await;
+
+    let Some(out) = out else {
+        panic!("Worktree not selected")
+    };
+
+    let selected_items = out
+        .selected_items
+        .iter()
+        .map(|selected_item| {
+            (**selected_item)
+                .as_any()
+                .downcast_ref::<String>()
+                .unwrap()
+                .to_owned()
+        })
+        .collect::<Vec<String>>();
+
+    handle_final_key(&out, &selected_items)?;
+
+    let selected_item = selected_items.first().expect("Worktree not selected");
+
+    // Items are unique, as each one names the common dir or a repository name used once
+    let target = targets
+        .iter()
+        .find(|target| &target.item == selected_item)
+        .context("Selected worktree not found")?;
+
+    if let Ok(repo) = Repository::open(&target.common_dir) {
+        // Switching within the repository of the current directory can be undone with `-`
+        let current_name = Repository::discover(".")
+            .ok()
+            .filter(|current_repo| {
+                get_common_dir(current_repo)
+                    .components()
+                    .eq(target.common_dir.components())
+            })
+            .and_then(|current_repo| get_current_name(&current_repo));
+
+        record_switch(&repo, current_name, &target.name);
+    }
+
+    Ok(format!("cd {}", target.path.display()))
+}
+
+/// Appends the aliases and the lock reason of a locked worktree to its picker item, so both can
+/// be searched for
+fn add_markers(worktree: &WorktreeInfo, item: String) -> String {
+    let item = if worktree.aliases.is_empty() {
+        item
+    } else {
+        format!("{} [alias: {}]", item, worktree.aliases.join(", "))
+    };
+
+    match &worktree.locked {
+        Some(reason) if !reason.is_empty() => format!("{} [locked: {}]", item, reason),
+        Some(_) => format!("{} [locked]", item),
+        None => item,
+    }
+}
+
+/// Worktree names cannot contain spaces, so the name is everything before the first one
+fn get_worktree_name_from_item(item: &str) -> String {
+    item.split(' ').next().unwrap_or(item).to_string()
+}
+
+/// Lets the user pick some of `worktrees`, returns the names of the picked ones
+pub(crate) async fn select_worktrees(
+    worktrees: &[WorktreeInfo],
+    query: Option<String>,
+) -> Result<Vec<String>> {
+    let items = worktrees
+        .iter()
+        .map(|worktree| add_markers(worktree, worktree.name.clone()))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let Some(out) = get_fuzzy_options(query, true, String::from("Worktree"), items).await else {
+        bail!("No worktree selected");
+    };
+
+    let selected_items = out
+        .selected_items
+        .iter()
+        .map(|selected_item| {
+            (**selected_item)
+                .as_any()
+                .downcast_ref::<String>()
+                .unwrap()
+                .to_owned()
+        })
+        .collect::<Vec<String>>();
+
+    handle_final_key(&out, &selected_items)?;
+
+    Ok(selected_items
+        .iter()
+        .map(|selected_item| get_worktree_name_from_item(selected_item))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::utils::git::test_utils::{get_test_dir, init_repo};
+
+    use super::*;
+
+    const NO_FETCH: FetchSettings = FetchSettings {
+        quiet: true,
+        skip: true,
+        full: false,
+        depth: None,
+    };
+
+    #[tokio::test]
+    async fn local_branch_without_worktree_gets_one() {
+        let root = get_test_dir("change-branch-local");
+        let repo = init_repo(&root);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("local/only", &head, false).unwrap();
+
+        let branch_name = Some(String::from("local/only"));
+        let command = change_branch_of_bare_or_worktree_repo(
+            &repo,
+            &branch_name,
+            &None,
+            None,
+            false,
+            &NO_FETCH,
+        )
+        .await
+        .unwrap();
+
+        let worktree_name = get_worktree_by_branch_name(&repo, "local/only").unwrap();
+        assert_eq!(worktree_name, "local_only");
+        assert!(command.starts_with("cd "));
+        assert!(command.ends_with("local_only"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,22 @@
+/// Prints rows as left aligned columns below a header
+pub(crate) fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let header = header.map(String::from);
+
+    let mut widths = [0; N];
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join("  ");
+
+        println!("{}", line.trim_end());
+    }
+}
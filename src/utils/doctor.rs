@@ -0,0 +1,518 @@
+use std::{fmt, path::Path};
+
+use anyhow::Result;
+use git2::{Direction, Repository};
+
+use super::{
+    forge::{
+        bitbucket::BITBUCKET_TOKEN_ENV_VARS, detect_forge_kind, get_remote_url,
+        gitea::GITEA_TOKEN_ENV_VARS, github::get_github_api, gitlab::GITLAB_TOKEN_ENV_VARS,
+        retrieve_forge_access_token, ForgeKind,
+    },
+    git::{
+        common::{get_common_dir, get_root_repo_path},
+        credentials::get_credentials_callback,
+        repair::{repair_worktrees, RepairSettings, RepairStatus},
+    },
+    github::{
+        auth::GITHUB_HOST,
+        common::{retrieve_github_access_token, setup_octocrab_with_token},
+    },
+};
+
+const DEFAULT_FETCH_REFSPEC: &str = "+refs/heads/*:refs/remotes/origin/*";
+const ORIGIN_HEAD: &str = "refs/remotes/origin/HEAD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+    Fixed,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Ok => write!(f, "ok"),
+            CheckStatus::Warning => write!(f, "warning"),
+            CheckStatus::Error => write!(f, "error"),
+            CheckStatus::Fixed => write!(f, "fixed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct CheckResult {
+    pub check: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to solve the problem by hand
+    pub fix: Option<String>,
+    /// Whether `--fix` can solve the problem
+    pub fixable: bool,
+}
+
+impl CheckResult {
+    fn new(check: &str, status: CheckStatus, detail: String) -> Self {
+        CheckResult {
+            check: check.to_string(),
+            status,
+            detail,
+            fix: None,
+            fixable: false,
+        }
+    }
+
+    fn with_fix(mut self, fix: String) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// Applies the fix when asked to, otherwise marks the problem as fixable
+    fn fix_with<F>(mut self, apply: bool, fix: F) -> Self
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        if !apply {
+            self.fixable = true;
+            return self;
+        }
+
+        match fix() {
+            Ok(()) => {
+                self.status = CheckStatus::Fixed;
+                self.fix = None;
+            }
+            Err(e) => self.detail = format!("{}, fix failed: {:#}", self.detail, e),
+        }
+
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct DoctorSettings {
+    pub fix: bool,
+    pub offline: bool,
+}
+
+/// Whether the working directory holds a checkout of HEAD, judged by its top-level entries
+fn has_checkout(repo: &Repository) -> bool {
+    let Some(workdir) = repo.workdir() else {
+        return false;
+    };
+
+    let Ok(tree) = repo.head().and_then(|head| head.peel_to_tree()) else {
+        // Nothing is committed yet, e.g. right after `git init`
+        return true;
+    };
+
+    tree.is_empty()
+        || tree
+            .iter()
+            .any(|entry| entry.name().is_some_and(|name| workdir.join(name).exists()))
+}
+
+fn check_bare_config(common_dir: &Path) -> Result<CheckResult> {
+    let common_repo = Repository::open(common_dir)?;
+    let check = "core.bare";
+
+    let result = if common_repo.is_bare() {
+        if common_dir.join("index").exists() {
+            CheckResult::new(
+                check,
+                CheckStatus::Warning,
+                format!(
+                    "`{}` has an index, but is marked as bare, so the checkout next to it is ignored",
+                    common_dir.display()
+                ),
+            )
+            .with_fix(String::from(
+                "git config core.bare false, if the parent directory is meant to be a working tree",
+            ))
+        } else {
+            CheckResult::new(
+                check,
+                CheckStatus::Ok,
+                format!("bare repository at `{}`", common_dir.display()),
+            )
+        }
+    } else if has_checkout(&common_repo) {
+        CheckResult::new(
+            check,
+            CheckStatus::Ok,
+            format!("repository at `{}`", common_dir.display()),
+        )
+    } else {
+        // Only the user knows whether the repository is meant to be bare, so this is not fixed
+        CheckResult::new(
+            check,
+            CheckStatus::Warning,
+            format!(
+                "`{}` is not marked as bare, but its working tree has no checkout of HEAD",
+                common_dir.display()
+            ),
+        )
+        .with_fix(String::from(
+            "git config core.bare true, if the repository is meant to be bare",
+        ))
+    };
+
+    Ok(result)
+}
+
+fn check_fetch_refspec(repo: &Repository, fix: bool) -> Result<CheckResult> {
+    let remote = repo.find_remote("origin")?;
+    let refspecs = remote
+        .fetch_refspecs()?
+        .iter()
+        .flatten()
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+    let check = "fetch refspec";
+
+    if refspecs.is_empty() {
+        // Bare clones are made without one
+        return Ok(CheckResult::new(
+            check,
+            CheckStatus::Warning,
+            String::from(
+                "`origin` has no fetch refspec, `git fetch` does not update remote branches",
+            ),
+        )
+        .with_fix(format!(
+            "git config remote.origin.fetch '{}'",
+            DEFAULT_FETCH_REFSPEC
+        ))
+        .fix_with(fix, || {
+            Ok(repo.remote_add_fetch("origin", DEFAULT_FETCH_REFSPEC)?)
+        }));
+    }
+
+    if !refspecs
+        .iter()
+        .any(|refspec| refspec.contains(":refs/remotes/origin/"))
+    {
+        return Ok(CheckResult::new(
+            check,
+            CheckStatus::Warning,
+            format!(
+                "`{}` does not fetch into `refs/remotes/origin`",
+                refspecs.join(" ")
+            ),
+        )
+        .with_fix(format!(
+            "git config --add remote.origin.fetch '{}'",
+            DEFAULT_FETCH_REFSPEC
+        )));
+    }
+
+    Ok(CheckResult::new(check, CheckStatus::Ok, refspecs.join(" ")))
+}
+
+/// Connects to `origin` with the credentials used for fetching, returns its default branch
+fn check_connection(repo: &Repository) -> (CheckResult, Option<String>) {
+    let check = "connection";
+
+    let connect = || -> Result<(String, Option<String>)> {
+        let mut remote = repo.find_remote("origin")?;
+        let url = remote.url().unwrap_or_default().to_string();
+        let connection =
+            remote.connect_auth(Direction::Fetch, Some(get_credentials_callback(repo)), None)?;
+
+        let default_branch = connection
+            .default_branch()
+            .ok()
+            .and_then(|branch| branch.as_str().map(str::to_string))
+            .and_then(|branch| branch.strip_prefix("refs/heads/").map(str::to_string));
+
+        Ok((url, default_branch))
+    };
+
+    match connect() {
+        Ok((url, default_branch)) => (
+            CheckResult::new(check, CheckStatus::Ok, format!("connected to `{}`", url)),
+            default_branch,
+        ),
+        Err(e) => (
+            CheckResult::new(
+                check,
+                CheckStatus::Error,
+                format!("failed to connect to `origin`: {:#}", e),
+            )
+            .with_fix(String::from(
+                "check the remote URL and the SSH keys or credential helper used for it",
+            )),
+            None,
+        ),
+    }
+}
+
+fn check_origin_head(repo: &Repository, default_branch: Option<&str>, fix: bool) -> CheckResult {
+    let check = "origin/HEAD";
+
+    if let Ok(reference) = repo.find_reference(ORIGIN_HEAD) {
+        let target = reference.symbolic_target().unwrap_or_default().to_string();
+
+        return CheckResult::new(check, CheckStatus::Ok, target);
+    }
+
+    let result = CheckResult::new(
+        check,
+        CheckStatus::Warning,
+        String::from("not set, commands fall back to `main` or `master` as the base branch"),
+    )
+    .with_fix(String::from("git remote set-head origin --auto"));
+
+    let target = default_branch
+        .map(|branch| format!("refs/remotes/origin/{}", branch))
+        .filter(|target| repo.find_reference(target).is_ok());
+
+    // The remote branch has to be fetched before HEAD can point to it
+    match target {
+        Some(target) => result.fix_with(fix, || {
+            repo.reference_symbolic(ORIGIN_HEAD, &target, false, "doctor: set origin/HEAD")?;
+            Ok(())
+        }),
+        None => result,
+    }
+}
+
+async fn check_forge(repo: &Repository, offline: bool) -> CheckResult {
+    let check = "forge";
+
+    let forge =
+        get_remote_url(repo).and_then(|remote| Ok((detect_forge_kind(repo, &remote)?, remote)));
+
+    let (forge_kind, remote) = match forge {
+        Ok(forge) => forge,
+        Err(e) => {
+            return CheckResult::new(
+                check,
+                CheckStatus::Warning,
+                format!("{:#}, PR commands are unavailable", e),
+            );
+        }
+    };
+
+    let token_env_vars: &[&str] = match forge_kind {
+        ForgeKind::Github => {
+            let (host, api_url) = get_github_api(repo, &remote);
+
+            let Some(access_token) = retrieve_github_access_token(&host) else {
+                return CheckResult::new(
+                    check,
+                    CheckStatus::Warning,
+                    String::from("GitHub without a token, only public repositories work and requests are rate limited"),
+                )
+                .with_fix(if host == GITHUB_HOST {
+                    String::from("set GH_TOKEN or log in with `gh auth login`")
+                } else {
+                    format!(
+                        "set WORKTREE_CLI_GITHUB_TOKEN or log in with `gh auth login --hostname {}`",
+                        host
+                    )
+                });
+            };
+
+            if offline {
+                return CheckResult::new(
+                    check,
+                    CheckStatus::Ok,
+                    format!("GitHub, token from {} not validated", access_token.source),
+                );
+            }
+
+            return match setup_octocrab_with_token(Some(&access_token), api_url.as_deref()).await {
+                Ok(_) => CheckResult::new(
+                    check,
+                    CheckStatus::Ok,
+                    format!("GitHub, valid token from {}", access_token.source),
+                ),
+                Err(e) => CheckResult::new(check, CheckStatus::Error, format!("{:#}", e))
+                    .with_fix(format!("replace the token in {}", access_token.source)),
+            };
+        }
+        ForgeKind::Gitlab => &GITLAB_TOKEN_ENV_VARS,
+        ForgeKind::Gitea => &GITEA_TOKEN_ENV_VARS,
+        ForgeKind::Bitbucket => &BITBUCKET_TOKEN_ENV_VARS,
+    };
+
+    match retrieve_forge_access_token(token_env_vars, &remote.host) {
+        Some(_) => CheckResult::new(
+            check,
+            CheckStatus::Ok,
+            format!("{} at `{}` with a token", forge_kind, remote.host),
+        ),
+        None => CheckResult::new(
+            check,
+            CheckStatus::Warning,
+            format!(
+                "{} at `{}` without a token, only public repositories work",
+                forge_kind, remote.host
+            ),
+        )
+        .with_fix(format!("set {}", token_env_vars.join(" or "))),
+    }
+}
+
+fn check_worktrees(repo: &Repository, fix: bool) -> Result<Vec<CheckResult>> {
+    let repair_results = repair_worktrees(
+        repo,
+        RepairSettings {
+            dry_run: !fix,
+            // Relinking in place replaces a `.git` file, which is left to `repair`
+            moved_only: true,
+            ..Default::default()
+        },
+    )?;
+
+    // New worktrees are created in this directory, named after the worktree. Worktrees added
+    // from another worktree of a bare repository end up next to the common directory instead
+    let root_path = get_root_repo_path(repo)?;
+    let common_dir = get_common_dir(repo);
+    let root_paths = [Some(root_path.as_path()), common_dir.parent()];
+
+    let mut check_results = Vec::new();
+
+    for repair_result in repair_results {
+        let check = format!("worktree `{}`", repair_result.worktree);
+
+        let metadata_result = match repair_result.status {
+            RepairStatus::Ok => None,
+            RepairStatus::Repaired => Some(CheckResult::new(
+                &check,
+                CheckStatus::Fixed,
+                repair_result.detail,
+            )),
+            RepairStatus::NeedsRepair => {
+                let mut result = CheckResult::new(&check, CheckStatus::Error, repair_result.detail);
+                result.fixable = repair_result.moved;
+                Some(result.with_fix(String::from("git-worktree-cli repair")))
+            }
+            RepairStatus::Missing | RepairStatus::Pruned => Some(
+                CheckResult::new(&check, CheckStatus::Warning, repair_result.detail)
+                    .with_fix(String::from("git-worktree-cli repair --prune")),
+            ),
+            RepairStatus::Conflict => Some(
+                CheckResult::new(&check, CheckStatus::Error, repair_result.detail).with_fix(
+                    String::from("move the directory away, or `git worktree repair` it by hand"),
+                ),
+            ),
+            RepairStatus::Failed => Some(CheckResult::new(
+                &check,
+                CheckStatus::Error,
+                repair_result.detail,
+            )),
+        };
+
+        if let Some(metadata_result) = metadata_result {
+            check_results.push(metadata_result);
+
+            if repair_result.status != RepairStatus::Repaired {
+                continue;
+            }
+        }
+
+        let Some(worktree_path) = repair_result.path else {
+            continue;
+        };
+
+        let mut problems = Vec::new();
+
+        if !root_paths.contains(&worktree_path.parent()) {
+            problems.push(
+                CheckResult::new(
+                    &check,
+                    CheckStatus::Warning,
+                    format!(
+                        "`{}` is outside of `{}`",
+                        worktree_path.display(),
+                        root_path.display()
+                    ),
+                )
+                .with_fix(format!(
+                    "git worktree move {} {}",
+                    worktree_path.display(),
+                    root_path.join(&repair_result.worktree).display()
+                )),
+            );
+        }
+
+        let is_detached = repo
+            .find_worktree(&repair_result.worktree)
+            .and_then(|worktree| Repository::open_from_worktree(&worktree))
+            .and_then(|worktree_repo| worktree_repo.head_detached());
+
+        if let Ok(true) = is_detached {
+            problems.push(
+                CheckResult::new(
+                    &check,
+                    CheckStatus::Warning,
+                    String::from("HEAD is detached"),
+                )
+                .with_fix(format!(
+                    "git -C {} switch <branch>",
+                    worktree_path.display()
+                )),
+            );
+        }
+
+        if problems.is_empty() {
+            check_results.push(CheckResult::new(
+                &check,
+                CheckStatus::Ok,
+                worktree_path.display().to_string(),
+            ));
+        }
+
+        check_results.extend(problems);
+    }
+
+    Ok(check_results)
+}
+
+/// Checks the repository configuration, the remote and its credentials, the forge token and
+/// the worktree metadata, fixing what can be fixed without losing anything when asked to
+pub(crate) async fn run_checks(
+    repo: &Repository,
+    settings: &DoctorSettings,
+) -> Result<Vec<CheckResult>> {
+    let common_dir = get_common_dir(repo);
+
+    let mut check_results = vec![check_bare_config(&common_dir)?];
+
+    if repo.find_remote("origin").is_err() {
+        check_results.push(
+            CheckResult::new(
+                "origin",
+                CheckStatus::Error,
+                String::from("no `origin` remote, branches cannot be fetched"),
+            )
+            .with_fix(String::from("git remote add origin <url>")),
+        );
+    } else {
+        check_results.push(check_fetch_refspec(repo, settings.fix)?);
+
+        let default_branch = if settings.offline {
+            None
+        } else {
+            let (check_result, default_branch) = check_connection(repo);
+            check_results.push(check_result);
+
+            default_branch
+        };
+
+        check_results.push(check_origin_head(
+            repo,
+            default_branch.as_deref(),
+            settings.fix,
+        ));
+        check_results.push(check_forge(repo, settings.offline).await);
+    }
+
+    check_results.extend(check_worktrees(repo, settings.fix)?);
+
+    Ok(check_results)
+}
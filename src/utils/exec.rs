@@ -0,0 +1,183 @@
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    path::Path,
+    process::{ExitStatus, Stdio},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use futures::future::join_all;
+use git2::Repository;
+use glob::Pattern;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Command,
+    spawn,
+    sync::Semaphore,
+};
+
+use super::{
+    git::{is_branch_clear, worktree::WorktreeInfo},
+    signal::{cancellable, is_cancelled},
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct WorktreeFilter {
+    /// Worktree names, aliases have to be resolved already
+    pub names: Vec<String>,
+    pub branch: Option<Pattern>,
+    pub dirty: Option<bool>,
+}
+
+impl WorktreeFilter {
+    fn matches(&self, repo: &Repository, worktree: &WorktreeInfo) -> bool {
+        if !self.names.is_empty() && !self.names.contains(&worktree.name) {
+            return false;
+        }
+
+        if let Some(pattern) = &self.branch {
+            // Detached worktrees have no branch to match
+            match &worktree.branch {
+                Some(branch) if pattern.matches(branch) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(dirty) = self.dirty {
+            let worktree_repo = repo
+                .find_worktree(&worktree.name)
+                .and_then(|worktree| Repository::open_from_worktree(&worktree));
+
+            match worktree_repo {
+                Ok(worktree_repo) if is_branch_clear(&worktree_repo) != dirty => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+pub(crate) fn filter_worktrees(
+    repo: &Repository,
+    worktrees: Vec<WorktreeInfo>,
+    filter: &WorktreeFilter,
+) -> Vec<WorktreeInfo> {
+    worktrees
+        .into_iter()
+        .filter(|worktree| filter.matches(repo, worktree))
+        .collect()
+}
+
+/// Copies `reader` line by line to stdout or stderr, prefixed with the worktree name
+async fn forward_output<R>(reader: R, prefix: Arc<String>, to_stderr: bool)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).split(b'\n');
+
+    while let Ok(Some(line)) = lines.next_segment().await {
+        // Lock per line, so output of parallel commands is interleaved by whole lines
+        let _ = if to_stderr {
+            let mut stderr = io::stderr().lock();
+            stderr
+                .write_all(prefix.as_bytes())
+                .and_then(|_| stderr.write_all(&line))
+                .and_then(|_| stderr.write_all(b"\n"))
+        } else {
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(prefix.as_bytes())
+                .and_then(|_| stdout.write_all(&line))
+                .and_then(|_| stdout.write_all(b"\n"))
+        };
+    }
+}
+
+async fn run_command(
+    worktree_name: &str,
+    worktree_path: &Path,
+    command: &[OsString],
+) -> Result<ExitStatus> {
+    let (program, args) = command
+        .split_first()
+        .context("No command to run was given")?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(worktree_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{}`", program.to_string_lossy()))?;
+
+    let prefix = Arc::new(format!("[{}] ", worktree_name));
+
+    let stdout = child.stdout.take().map(|stdout| {
+        let prefix = Arc::clone(&prefix);
+        spawn(forward_output(stdout, prefix, false))
+    });
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| spawn(forward_output(stderr, prefix, true)));
+
+    let status = child.wait().await?;
+
+    for output in [stdout, stderr].into_iter().flatten() {
+        let _ = output.await;
+    }
+
+    Ok(status)
+}
+
+/// Runs `command` in every given worktree, at most `parallel` at once, and returns the
+/// exit code of each worktree in the given order
+pub(crate) async fn exec_in_worktrees(
+    worktrees: Vec<WorktreeInfo>,
+    command: Vec<OsString>,
+    parallel: usize,
+) -> Result<Vec<(String, Result<i32>)>> {
+    if command.is_empty() {
+        bail!("No command to run was given");
+    }
+
+    let command = Arc::new(command);
+    let semaphore = Arc::new(Semaphore::new(parallel));
+
+    // Ctrl-C reaches the running commands directly, the pending ones are not started
+    let _guard = cancellable();
+
+    let (names, tasks): (Vec<String>, Vec<_>) = worktrees
+        .into_iter()
+        .map(|worktree| {
+            let command = Arc::clone(&command);
+            let semaphore = Arc::clone(&semaphore);
+            let name = worktree.name.clone();
+
+            let task = spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+
+                if is_cancelled() {
+                    bail!("Cancelled");
+                }
+
+                let status = run_command(&worktree.name, &worktree.path, &command).await?;
+
+                // Commands killed by a signal have no exit code
+                Ok(status.code().unwrap_or(1))
+            });
+
+            (name, task)
+        })
+        .unzip();
+
+    let exit_codes = join_all(tasks)
+        .await
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|e| Err(e.into())));
+
+    Ok(names.into_iter().zip(exit_codes).collect())
+}
@@ -0,0 +1,42 @@
+use std::{fs, path::Path, process};
+
+use anyhow::{Context, Result};
+
+/// Writes to a temporary file next to `path` first, so readers never see a half-written file
+/// and concurrent writers replace each other's file as a whole
+pub(crate) fn write_atomically(path: &Path, contents: &str) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    fs::write(&temp_path, contents)
+        .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+
+    fs::rename(&temp_path, path).with_context(|| {
+        let _ = fs::remove_file(&temp_path);
+
+        format!("Failed to write {}", path.display())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn file_is_replaced_without_leaving_the_temporary_file() {
+        let dir = env::temp_dir().join(format!("worktree-cli-file-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.json");
+
+        write_atomically(&path, "first").unwrap();
+        write_atomically(&path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use git2::Repository;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+
+use super::{
+    build_http_client, get_forge_base_url, remote::RemoteUrl, retrieve_forge_access_token,
+    ChangeRequest, ChangeRequestState, Forge, ForgeKind,
+};
+
+pub(crate) const BITBUCKET_TOKEN_ENV_VARS: [&str; 2] =
+    ["WORKTREE_CLI_BITBUCKET_TOKEN", "BITBUCKET_TOKEN"];
+
+pub(crate) struct BitbucketForge {
+    client: Client,
+    api_url: String,
+    project: String,
+    repo: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequestRef {
+    display_id: String,
+    repository: RefRepository,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefRepository {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    href: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PullRequestLinks {
+    #[serde(rename = "self", default)]
+    self_links: Vec<Link>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequest {
+    id: u64,
+    title: String,
+    state: String,
+    // Only reported by Bitbucket Server 8.18+
+    #[serde(default)]
+    draft: bool,
+    from_ref: PullRequestRef,
+    to_ref: PullRequestRef,
+    #[serde(default)]
+    links: PullRequestLinks,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PagedPullRequests {
+    values: Vec<PullRequest>,
+    is_last_page: bool,
+    next_page_start: Option<u64>,
+}
+
+fn to_change_request(pr: PullRequest) -> ChangeRequest {
+    let state = match pr.state.as_str() {
+        "MERGED" => ChangeRequestState::Merged,
+        "DECLINED" => ChangeRequestState::Closed,
+        _ => ChangeRequestState::Open,
+    };
+
+    ChangeRequest {
+        number: pr.id,
+        title: pr.title,
+        source_branch: pr.from_ref.display_id,
+        from_fork: pr.from_ref.repository.id != pr.to_ref.repository.id,
+        draft: pr.draft,
+        state,
+        url: pr.links.self_links.into_iter().next().map(|link| link.href),
+    }
+}
+
+impl BitbucketForge {
+    pub(crate) fn new(repo: &Repository, remote: &RemoteUrl) -> Result<Self> {
+        let segments = remote.path.rsplitn(4, '/').collect::<Vec<&str>>();
+
+        let (context, project, name) = match segments.as_slice() {
+            // HTTP clone URLs look like `[<context>/]scm/<project>/<repo>`
+            [name, project, "scm", context @ ..] => (
+                context.first().copied().unwrap_or_default(),
+                *project,
+                *name,
+            ),
+            // SSH clone URLs look like `<project>/<repo>`
+            [name, project] => ("", *project, *name),
+            _ => bail!(
+                "Failed to get project and repository from `{}`",
+                remote.path
+            ),
+        };
+
+        let base_url = get_forge_base_url(repo, remote);
+        let base_url = if context.is_empty() || base_url != remote.web_url() {
+            base_url
+        } else {
+            format!("{}/{}", base_url, context)
+        };
+
+        Ok(Self {
+            client: build_http_client()?,
+            api_url: format!("{}/rest/api/1.0", base_url),
+            project: project.to_string(),
+            repo: name.to_string(),
+            token: retrieve_forge_access_token(&BITBUCKET_TOKEN_ENV_VARS, &remote.host),
+        })
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let request = self.client.get(format!(
+            "{}/projects/{}/repos/{}{}",
+            self.api_url, self.project, self.repo, path
+        ));
+
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for BitbucketForge {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Bitbucket
+    }
+
+    async fn list_change_requests(&self, state: ChangeRequestState) -> Result<Vec<ChangeRequest>> {
+        let pr_state = match state {
+            ChangeRequestState::Open => "OPEN",
+            ChangeRequestState::Closed => "DECLINED",
+            ChangeRequestState::Merged => "MERGED",
+        };
+
+        let mut pull_requests = Vec::new();
+        let mut start = 0;
+
+        loop {
+            let response = self
+                .get("/pull-requests")
+                .query(&[
+                    ("state", pr_state.to_string()),
+                    ("limit", String::from("100")),
+                    ("start", start.to_string()),
+                ])
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!("Failed to list pull requests: {}", response.status());
+            }
+
+            let page = response.json::<PagedPullRequests>().await?;
+
+            pull_requests.extend(page.values);
+
+            match page.next_page_start {
+                Some(next_page_start) if !page.is_last_page => start = next_page_start,
+                _ => break,
+            }
+        }
+
+        Ok(pull_requests.into_iter().map(to_change_request).collect())
+    }
+
+    async fn get_change_request(&self, number: u64) -> Result<ChangeRequest> {
+        let response = self
+            .get(&format!("/pull-requests/{}", number))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!(
+                "Failed to get pull request #{}: {}",
+                number,
+                response.status()
+            );
+        }
+
+        Ok(to_change_request(response.json::<PullRequest>().await?))
+    }
+
+    fn head_ref(&self, change_request: &ChangeRequest) -> String {
+        format!("refs/pull-requests/{}/from", change_request.number)
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use git2::Repository;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+
+use super::{
+    build_http_client, get_forge_base_url, remote::RemoteUrl, retrieve_forge_access_token,
+    ChangeRequest, ChangeRequestState, Forge, ForgeKind,
+};
+
+pub(crate) const GITEA_TOKEN_ENV_VARS: [&str; 3] =
+    ["WORKTREE_CLI_GITEA_TOKEN", "GITEA_TOKEN", "FORGEJO_TOKEN"];
+
+const PAGE_LIMIT: usize = 50;
+
+pub(crate) struct GiteaForge {
+    client: Client,
+    api_url: String,
+    owner: String,
+    repo: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequestBranch {
+    #[serde(rename = "ref")]
+    ref_field: String,
+    repo_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    // Only reported by Gitea 1.22+ and Forgejo 8+
+    #[serde(default)]
+    draft: bool,
+    html_url: Option<String>,
+    head: PullRequestBranch,
+    base: PullRequestBranch,
+}
+
+fn to_change_request(pr: PullRequest) -> ChangeRequest {
+    let state = if pr.merged {
+        ChangeRequestState::Merged
+    } else if pr.state == "closed" {
+        ChangeRequestState::Closed
+    } else {
+        ChangeRequestState::Open
+    };
+
+    // Older versions mark drafts with a title prefix only
+    let draft = pr.draft || pr.title.starts_with("WIP:") || pr.title.starts_with("[WIP]");
+
+    ChangeRequest {
+        number: pr.number,
+        title: pr.title,
+        source_branch: pr.head.ref_field,
+        from_fork: pr.head.repo_id != pr.base.repo_id,
+        draft,
+        state,
+        url: pr.html_url,
+    }
+}
+
+impl GiteaForge {
+    pub(crate) fn new(repo: &Repository, remote: &RemoteUrl) -> Result<Self> {
+        let (owner, name) = remote.owner_and_repo()?;
+
+        Ok(Self {
+            client: build_http_client()?,
+            api_url: format!("{}/api/v1", get_forge_base_url(repo, remote)),
+            owner: owner.to_string(),
+            repo: name.to_string(),
+            token: retrieve_forge_access_token(&GITEA_TOKEN_ENV_VARS, &remote.host),
+        })
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let request = self.client.get(format!(
+            "{}/repos/{}/{}{}",
+            self.api_url, self.owner, self.repo, path
+        ));
+
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("token {}", token)),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for GiteaForge {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Gitea
+    }
+
+    async fn list_change_requests(&self, state: ChangeRequestState) -> Result<Vec<ChangeRequest>> {
+        let pr_state = match state {
+            ChangeRequestState::Open => "open",
+            ChangeRequestState::Closed | ChangeRequestState::Merged => "closed",
+        };
+
+        let mut pull_requests = Vec::new();
+
+        for page in 1.. {
+            let response = self
+                .get("/pulls")
+                .query(&[
+                    ("state", pr_state.to_string()),
+                    ("limit", PAGE_LIMIT.to_string()),
+                    ("page", page.to_string()),
+                ])
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!("Failed to list pull requests: {}", response.status());
+            }
+
+            // Servers cap `limit` at their own maximum, so a short page is not necessarily the last
+            let total_count = response
+                .headers()
+                .get("X-Total-Count")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<usize>().ok());
+
+            let page_pull_requests = response.json::<Vec<PullRequest>>().await?;
+
+            if page_pull_requests.is_empty() {
+                break;
+            }
+
+            pull_requests.extend(page_pull_requests);
+
+            if total_count.is_some_and(|total_count| pull_requests.len() >= total_count) {
+                break;
+            }
+        }
+
+        Ok(pull_requests
+            .into_iter()
+            .map(to_change_request)
+            .filter(|pr| match state {
+                ChangeRequestState::Closed => pr.state == ChangeRequestState::Closed,
+                ChangeRequestState::Merged => pr.state == ChangeRequestState::Merged,
+                ChangeRequestState::Open => true,
+            })
+            .collect())
+    }
+
+    async fn get_change_request(&self, number: u64) -> Result<ChangeRequest> {
+        let response = self.get(&format!("/pulls/{}", number)).send().await?;
+
+        if !response.status().is_success() {
+            bail!(
+                "Failed to get pull request #{}: {}",
+                number,
+                response.status()
+            );
+        }
+
+        Ok(to_change_request(response.json::<PullRequest>().await?))
+    }
+
+    fn head_ref(&self, change_request: &ChangeRequest) -> String {
+        format!("refs/pull/{}/head", change_request.number)
+    }
+}
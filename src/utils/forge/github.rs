@@ -0,0 +1,163 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use octocrab::{
+    models::{pulls::PullRequest, IssueState},
+    params::State,
+    Octocrab,
+};
+
+use git2::Repository;
+
+use crate::utils::github::{
+    auth::GITHUB_HOST,
+    common::{retrieve_github_access_token, setup_octocrab_with_token},
+};
+
+use super::{
+    get_forge_base_url, remote::RemoteUrl, ChangeRequest, ChangeRequestState, Forge, ForgeKind,
+};
+
+pub(crate) struct GithubForge {
+    octocrab: Octocrab,
+    owner: String,
+    repo: String,
+    /// Path of the API URL, e.g. `/api/v3` for GitHub Enterprise
+    api_path: String,
+}
+
+/// Host the API requests go to and the API URL for GitHub Enterprise, both follow
+/// `worktree-cli.forge-url` when it is set
+pub(crate) fn get_github_api(repo: &Repository, remote: &RemoteUrl) -> (String, Option<String>) {
+    let base_url = get_forge_base_url(repo, remote);
+
+    let authority = base_url
+        .split_once("://")
+        .map_or(base_url.as_str(), |(_, authority)| authority);
+    let host = authority
+        .split(['/', ':'])
+        .next()
+        .unwrap_or(authority)
+        .to_string();
+
+    // GitHub Enterprise serves the REST API under `/api/v3` of its web URL
+    let api_url =
+        (base_url != format!("https://{}", GITHUB_HOST)).then(|| format!("{}/api/v3", base_url));
+
+    (host, api_url)
+}
+
+impl GithubForge {
+    pub(crate) async fn new(repo: &Repository, remote: &RemoteUrl) -> Result<Self> {
+        let (owner, name) = remote.owner_and_repo()?;
+        let (host, api_url) = get_github_api(repo, remote);
+
+        let access_token = retrieve_github_access_token(&host);
+        let (octocrab, _) =
+            setup_octocrab_with_token(access_token.as_ref(), api_url.as_deref()).await?;
+
+        let api_path = api_url
+            .as_deref()
+            .and_then(|api_url| reqwest::Url::parse(api_url).ok())
+            .map(|api_url| api_url.path().trim_end_matches('/').to_string())
+            .unwrap_or_default();
+
+        Ok(Self {
+            octocrab,
+            owner: owner.to_string(),
+            repo: name.to_string(),
+            api_path,
+        })
+    }
+}
+
+fn to_change_request(pr: PullRequest) -> ChangeRequest {
+    let state = if pr.merged_at.is_some() {
+        ChangeRequestState::Merged
+    } else if pr.state == Some(IssueState::Closed) {
+        ChangeRequestState::Closed
+    } else {
+        ChangeRequestState::Open
+    };
+
+    // The head repository is gone when the fork was deleted
+    let from_fork = match (&pr.head.repo, &pr.base.repo) {
+        (Some(head_repo), Some(base_repo)) => head_repo.id != base_repo.id,
+        _ => true,
+    };
+
+    ChangeRequest {
+        number: pr.number,
+        title: pr.title.unwrap_or_default(),
+        source_branch: pr.head.ref_field,
+        from_fork,
+        draft: pr.draft.unwrap_or(false),
+        state,
+        url: pr.html_url.map(|url| url.to_string()),
+    }
+}
+
+#[async_trait]
+impl Forge for GithubForge {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Github
+    }
+
+    async fn list_change_requests(&self, state: ChangeRequestState) -> Result<Vec<ChangeRequest>> {
+        let pr_state = match state {
+            ChangeRequestState::Open => State::Open,
+            ChangeRequestState::Closed | ChangeRequestState::Merged => State::Closed,
+        };
+
+        let mut page = self
+            .octocrab
+            .pulls(&self.owner, &self.repo)
+            .list()
+            .state(pr_state)
+            .per_page(100)
+            .send()
+            .await?;
+
+        let mut prs = page.take_items();
+
+        // Octocrab puts the path of the API URL in front of the absolute next links again, so
+        // they are made relative to it first
+        while let Some(next) = page.next.take() {
+            let path_and_query = next.path_and_query().map_or("", |path| path.as_str());
+            let next = path_and_query
+                .strip_prefix(&self.api_path)
+                .unwrap_or(path_and_query)
+                .parse()?;
+
+            let Some(next_page) = self.octocrab.get_page(&Some(next)).await? else {
+                break;
+            };
+
+            page = next_page;
+            prs.extend(page.take_items());
+        }
+
+        Ok(prs
+            .into_iter()
+            .map(to_change_request)
+            .filter(|pr| match state {
+                ChangeRequestState::Closed => pr.state == ChangeRequestState::Closed,
+                ChangeRequestState::Merged => pr.state == ChangeRequestState::Merged,
+                ChangeRequestState::Open => true,
+            })
+            .collect())
+    }
+
+    async fn get_change_request(&self, number: u64) -> Result<ChangeRequest> {
+        let pr = self
+            .octocrab
+            .pulls(&self.owner, &self.repo)
+            .get(number)
+            .await?;
+
+        Ok(to_change_request(pr))
+    }
+
+    fn head_ref(&self, change_request: &ChangeRequest) -> String {
+        format!("refs/pull/{}/head", change_request.number)
+    }
+}
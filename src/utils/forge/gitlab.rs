@@ -0,0 +1,143 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use git2::Repository;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+
+use super::{
+    build_http_client, get_forge_base_url, remote::RemoteUrl, retrieve_forge_access_token,
+    ChangeRequest, ChangeRequestState, Forge, ForgeKind,
+};
+
+pub(crate) const GITLAB_TOKEN_ENV_VARS: [&str; 2] = ["WORKTREE_CLI_GITLAB_TOKEN", "GITLAB_TOKEN"];
+
+pub(crate) struct GitlabForge {
+    client: Client,
+    api_url: String,
+    project: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    title: String,
+    source_branch: String,
+    source_project_id: u64,
+    target_project_id: u64,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    work_in_progress: bool,
+    state: String,
+    web_url: Option<String>,
+}
+
+fn to_change_request(mr: MergeRequest) -> ChangeRequest {
+    let state = match mr.state.as_str() {
+        "merged" => ChangeRequestState::Merged,
+        "closed" => ChangeRequestState::Closed,
+        _ => ChangeRequestState::Open,
+    };
+
+    ChangeRequest {
+        number: mr.iid,
+        title: mr.title,
+        source_branch: mr.source_branch,
+        from_fork: mr.source_project_id != mr.target_project_id,
+        draft: mr.draft || mr.work_in_progress,
+        state,
+        url: mr.web_url,
+    }
+}
+
+impl GitlabForge {
+    pub(crate) fn new(repo: &Repository, remote: &RemoteUrl) -> Result<Self> {
+        Ok(Self {
+            client: build_http_client()?,
+            api_url: format!("{}/api/v4", get_forge_base_url(repo, remote)),
+            // Projects can be nested in several groups, the whole path is the project id
+            project: remote.path.replace('/', "%2F"),
+            token: retrieve_forge_access_token(&GITLAB_TOKEN_ENV_VARS, &remote.host),
+        })
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let request = self.client.get(format!(
+            "{}/projects/{}{}",
+            self.api_url, self.project, path
+        ));
+
+        match &self.token {
+            Some(token) => request.header("PRIVATE-TOKEN", token),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl Forge for GitlabForge {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Gitlab
+    }
+
+    async fn list_change_requests(&self, state: ChangeRequestState) -> Result<Vec<ChangeRequest>> {
+        let mr_state = match state {
+            ChangeRequestState::Open => "opened",
+            ChangeRequestState::Closed => "closed",
+            ChangeRequestState::Merged => "merged",
+        };
+
+        let mut merge_requests = Vec::new();
+        let mut page = String::from("1");
+
+        loop {
+            let response = self
+                .get("/merge_requests")
+                .query(&[("state", mr_state), ("per_page", "100"), ("page", &page)])
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!("Failed to list merge requests: {}", response.status());
+            }
+
+            let next_page = response
+                .headers()
+                .get("x-next-page")
+                .and_then(|next_page| next_page.to_str().ok())
+                .filter(|next_page| !next_page.is_empty())
+                .map(|next_page| next_page.to_string());
+
+            merge_requests.extend(response.json::<Vec<MergeRequest>>().await?);
+
+            match next_page {
+                Some(next_page) => page = next_page,
+                None => break,
+            }
+        }
+
+        Ok(merge_requests.into_iter().map(to_change_request).collect())
+    }
+
+    async fn get_change_request(&self, number: u64) -> Result<ChangeRequest> {
+        let response = self
+            .get(&format!("/merge_requests/{}", number))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!(
+                "Failed to get merge request !{}: {}",
+                number,
+                response.status()
+            );
+        }
+
+        Ok(to_change_request(response.json::<MergeRequest>().await?))
+    }
+
+    fn head_ref(&self, change_request: &ChangeRequest) -> String {
+        format!("refs/merge-requests/{}/head", change_request.number)
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    env,
+    fmt::{self, Display},
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use git2::Repository;
+use reqwest::Client;
+
+use crate::utils::github::auth::get_git_credential_token;
+
+use self::{
+    bitbucket::BitbucketForge,
+    gitea::GiteaForge,
+    github::GithubForge,
+    gitlab::GitlabForge,
+    remote::{parse_remote_url, RemoteUrl},
+};
+
+pub(crate) mod bitbucket;
+pub(crate) mod gitea;
+pub(crate) mod github;
+pub(crate) mod gitlab;
+pub(crate) mod pr;
+pub(crate) mod remote;
+
+const FORGE_CONFIG_KEY: &str = "worktree-cli.forge";
+const FORGE_URL_CONFIG_KEY: &str = "worktree-cli.forge-url";
+const BITBUCKET_CLOUD_HOST: &str = "bitbucket.org";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ForgeKind {
+    Github,
+    Gitlab,
+    Gitea,
+    Bitbucket,
+}
+
+impl Display for ForgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ForgeKind::Github => "GitHub",
+            ForgeKind::Gitlab => "GitLab",
+            ForgeKind::Gitea => "Gitea/Forgejo",
+            ForgeKind::Bitbucket => "Bitbucket Server",
+        };
+
+        write!(f, "{}", kind)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ChangeRequestState {
+    Open,
+    Closed,
+    Merged,
+}
+
+/// A pull request (GitHub, Gitea, Bitbucket) or merge request (GitLab)
+#[derive(Debug, Clone)]
+pub(crate) struct ChangeRequest {
+    pub number: u64,
+    pub title: String,
+    pub source_branch: String,
+    /// The source branch lives in another repository, a branch of the same name on origin is
+    /// unrelated to it
+    pub from_fork: bool,
+    pub draft: bool,
+    pub state: ChangeRequestState,
+    pub url: Option<String>,
+}
+
+#[async_trait]
+pub(crate) trait Forge: Send + Sync {
+    fn kind(&self) -> ForgeKind;
+
+    async fn list_change_requests(&self, state: ChangeRequestState) -> Result<Vec<ChangeRequest>>;
+
+    async fn get_change_request(&self, number: u64) -> Result<ChangeRequest>;
+
+    /// Ref on the remote pointing to the head commit of the change request,
+    /// available even when the source branch lives in a fork
+    fn head_ref(&self, change_request: &ChangeRequest) -> String;
+}
+
+pub(crate) fn detect_forge_kind(repo: &Repository, remote: &RemoteUrl) -> Result<ForgeKind> {
+    let kind = detect_configured_or_hosted_forge_kind(repo, remote)?;
+
+    // Bitbucket Cloud has a different API (2.0) than Bitbucket Server and Data Center
+    if kind == ForgeKind::Bitbucket && remote.host.eq_ignore_ascii_case(BITBUCKET_CLOUD_HOST) {
+        bail!(
+            "Bitbucket Cloud (`{}`) is not supported, only Bitbucket Server and Data Center are",
+            BITBUCKET_CLOUD_HOST
+        );
+    }
+
+    Ok(kind)
+}
+
+fn detect_configured_or_hosted_forge_kind(
+    repo: &Repository,
+    remote: &RemoteUrl,
+) -> Result<ForgeKind> {
+    if let Ok(forge) = repo
+        .config()
+        .and_then(|config| config.get_string(FORGE_CONFIG_KEY))
+    {
+        return match forge.to_lowercase().as_str() {
+            "github" => Ok(ForgeKind::Github),
+            "gitlab" => Ok(ForgeKind::Gitlab),
+            "gitea" | "forgejo" => Ok(ForgeKind::Gitea),
+            "bitbucket" => Ok(ForgeKind::Bitbucket),
+            _ => bail!("Unsupported forge `{}` in `{}`", forge, FORGE_CONFIG_KEY),
+        };
+    }
+
+    let host = remote.host.to_lowercase();
+
+    if host == "github.com" {
+        Ok(ForgeKind::Github)
+    } else if host.contains("gitlab") {
+        Ok(ForgeKind::Gitlab)
+    } else if host.contains("gitea") || host.contains("forgejo") || host == "codeberg.org" {
+        Ok(ForgeKind::Gitea)
+    } else if host.contains("bitbucket") || remote.path.starts_with("scm/") {
+        Ok(ForgeKind::Bitbucket)
+    } else {
+        bail!(
+            "Failed to detect forge for `{}`. Set it with `git config {} <github|gitlab|gitea|bitbucket>`",
+            remote.host,
+            FORGE_CONFIG_KEY
+        )
+    }
+}
+
+/// Base URL of the forge, `worktree-cli.forge-url` overrides the one derived from the remote
+pub(crate) fn get_forge_base_url(repo: &Repository, remote: &RemoteUrl) -> String {
+    repo.config()
+        .and_then(|config| config.get_string(FORGE_URL_CONFIG_KEY))
+        .unwrap_or_else(|_| remote.web_url())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub(crate) fn retrieve_forge_access_token(env_vars: &[&str], host: &str) -> Option<String> {
+    env_vars
+        .iter()
+        .find_map(|var| env::var(var).ok().filter(|token| !token.trim().is_empty()))
+        .or_else(|| get_git_credential_token(host))
+}
+
+pub(crate) fn build_http_client() -> Result<Client> {
+    Ok(Client::builder()
+        .user_agent(concat!("git-worktree-cli/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+pub(crate) fn get_remote_url(repo: &Repository) -> Result<RemoteUrl> {
+    // Read the configured URL, `Remote::url` has `url.<base>.insteadOf` rewrites applied
+    let url = match repo.config()?.get_string("remote.origin.url") {
+        Ok(url) => url,
+        Err(_) => repo
+            .find_remote("origin")?
+            .url()
+            .ok_or_else(|| anyhow::anyhow!("Remote URL not found"))?
+            .to_string(),
+    };
+
+    parse_remote_url(&url)
+}
+
+pub(crate) async fn get_forge(repo: &Repository) -> Result<Box<dyn Forge>> {
+    let remote = get_remote_url(repo)?;
+
+    let forge: Box<dyn Forge> = match detect_forge_kind(repo, &remote)? {
+        ForgeKind::Github => Box::new(GithubForge::new(repo, &remote).await?),
+        ForgeKind::Gitlab => Box::new(GitlabForge::new(repo, &remote)?),
+        ForgeKind::Gitea => Box::new(GiteaForge::new(repo, &remote)?),
+        ForgeKind::Bitbucket => Box::new(BitbucketForge::new(repo, &remote)?),
+    };
+
+    debug!("Using {} forge for `{}`", forge.kind(), remote.host);
+
+    Ok(forge)
+}
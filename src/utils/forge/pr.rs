@@ -0,0 +1,724 @@
+use std::{collections::HashSet, fmt, path::Path, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures::future::join_all;
+use git2::{BranchType, Repository};
+use serde::Serialize;
+use tokio::{spawn, sync::Semaphore, task::spawn_blocking};
+
+use crate::{
+    utils::{
+        cli::{add_branch_from_reference, add_worktree_from_branch, table::print_table},
+        git::{
+            branch::{get_branch, BranchInfo},
+            fetch::{fetch_branches, FetchSettings},
+            open_repo,
+            worktree::{worktree_exists_by_branch_name, AddKind},
+        },
+        retry::retry_with_backoff,
+        search::common::{get_fuzzy_options, handle_final_key},
+        signal::cancellable,
+    },
+    PRKind, PrSelection,
+};
+
+use super::{get_forge, ChangeRequest, ChangeRequestState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PrResultStatus {
+    Added,
+    Existed,
+    Failed,
+}
+
+impl fmt::Display for PrResultStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrResultStatus::Added => write!(f, "added"),
+            PrResultStatus::Existed => write!(f, "already existed"),
+            PrResultStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PrResult {
+    pub number: u64,
+    pub title: String,
+    pub branch: String,
+    pub url: Option<String>,
+    pub status: PrResultStatus,
+    pub command: Option<String>,
+    pub error: Option<String>,
+}
+
+impl PrResult {
+    fn new(pr: &ChangeRequest, outcome: Result<(String, AddKind)>) -> Self {
+        let (status, command, error) = match outcome {
+            Ok((command, AddKind::Added)) => (PrResultStatus::Added, Some(command), None),
+            Ok((command, AddKind::Existed)) => (PrResultStatus::Existed, Some(command), None),
+            Err(e) => (PrResultStatus::Failed, None, Some(format!("{:#}", e))),
+        };
+
+        PrResult {
+            number: pr.number,
+            title: pr.title.clone(),
+            branch: pr.source_branch.clone(),
+            url: pr.url.clone(),
+            status,
+            command,
+            error,
+        }
+    }
+}
+
+pub async fn add_workspace_by_pull_requests(
+    repo: &Repository,
+    pr_state: ChangeRequestState,
+    pr_kind: PRKind,
+    pr_selection: PrSelection,
+    pr_numbers: Vec<u64>,
+    fetch_settings: FetchSettings,
+    jobs: usize,
+) -> Result<Vec<PrResult>> {
+    let forge = get_forge(repo).await?;
+
+    let selected_prs = if pr_numbers.is_empty() {
+        let prs = forge.list_change_requests(pr_state).await?;
+
+        let filtered_prs = filter_prs(prs, repo, pr_kind);
+        select_prs(filtered_prs, pr_selection).await?
+    } else {
+        let mut prs = Vec::with_capacity(pr_numbers.len());
+
+        for pr_number in pr_numbers {
+            prs.push(forge.get_change_request(pr_number).await?);
+        }
+
+        prs
+    };
+
+    let mut pr_results = Vec::with_capacity(selected_prs.len());
+
+    // PRs from different forks can share a branch name, only one worktree can be created for it
+    let mut branch_names = HashSet::new();
+    let selected_prs = selected_prs
+        .into_iter()
+        .filter(|pr| {
+            let is_unique = branch_names.insert(pr.source_branch.clone());
+
+            if !is_unique {
+                warn!(
+                    "Skipping PR #{}, branch `{}` is used by another selected PR",
+                    pr.number, pr.source_branch
+                );
+
+                pr_results.push(PrResult::new(
+                    pr,
+                    Err(anyhow!(
+                        "Branch `{}` is used by another selected PR",
+                        pr.source_branch
+                    )),
+                ));
+            }
+
+            is_unique
+        })
+        .map(|pr| {
+            let head_ref = forge.head_ref(&pr);
+            (pr, head_ref)
+        })
+        .collect::<Vec<(ChangeRequest, String)>>();
+
+    // Fetch once for the whole batch, the worktrees are then created from local refs. Source
+    // branches of forks are not on origin, a branch of the same name there is unrelated
+    let branch_names = selected_prs
+        .iter()
+        .filter(|(pr, _)| !pr.from_fork)
+        .map(|(pr, _)| pr.source_branch.clone())
+        .collect::<Vec<String>>();
+    let head_refs = selected_prs
+        .iter()
+        .map(|(_, head_ref)| head_ref.clone())
+        .collect::<Vec<String>>();
+
+    retry_with_backoff("fetch PR branches", || async {
+        Ok(fetch_branches(
+            repo,
+            &branch_names,
+            &head_refs,
+            &fetch_settings,
+        )?)
+    })
+    .await?;
+
+    let repo_path = Arc::new(repo.path().to_path_buf());
+    let semaphore = Arc::new(Semaphore::new(jobs));
+
+    // Ctrl-C lets the running tasks finish, the pending ones fail as cancelled
+    let _guard = cancellable();
+
+    let (prs, tasks): (Vec<ChangeRequest>, Vec<_>) = selected_prs
+        .into_iter()
+        .map(|(pr, head_ref)| {
+            let repo_path = Arc::clone(&repo_path);
+            let semaphore = Arc::clone(&semaphore);
+            let task_pr = pr.clone();
+            let task = spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+
+                retry_with_backoff(&format!("add workspace for PR #{}", task_pr.number), || {
+                    let repo_path = Arc::clone(&repo_path);
+                    let task_pr = task_pr.clone();
+                    let head_ref = head_ref.clone();
+
+                    // git2 blocks, keep it off the threads driving the async tasks
+                    async move {
+                        spawn_blocking(move || {
+                            create_branch_for_pull_request(&repo_path, &task_pr, &head_ref)
+                        })
+                        .await?
+                    }
+                })
+                .await
+            });
+
+            (pr, task)
+        })
+        .unzip();
+
+    let results = join_all(tasks).await;
+
+    for (pr, result) in prs.iter().zip(results) {
+        let outcome = result.unwrap_or_else(|e| Err(e.into()));
+
+        if let Err(e) = &outcome {
+            error!("Failed to add workspace for PR #{}: {:?}", pr.number, e);
+        }
+
+        pr_results.push(PrResult::new(pr, outcome));
+    }
+
+    pr_results.sort_by_key(|pr_result| pr_result.number);
+
+    Ok(pr_results)
+}
+
+/// Prints one row per PR followed by a summary line
+pub(crate) fn print_pr_results_table(pr_results: &[PrResult]) {
+    let rows = pr_results
+        .iter()
+        .map(|pr_result| {
+            [
+                format!("#{}", pr_result.number),
+                pr_result.status.to_string(),
+                pr_result.branch.clone(),
+                pr_result
+                    .error
+                    .clone()
+                    .or_else(|| pr_result.command.clone())
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<[String; 4]>>();
+
+    print_table(["PR", "STATUS", "BRANCH", "DETAILS"], &rows);
+
+    let count = |status: PrResultStatus| {
+        pr_results
+            .iter()
+            .filter(|pr_result| pr_result.status == status)
+            .count()
+    };
+
+    println!(
+        "\n{} added, {} already existed, {} failed",
+        count(PrResultStatus::Added),
+        count(PrResultStatus::Existed),
+        count(PrResultStatus::Failed)
+    );
+}
+
+fn matches_pr_kind(pr: &ChangeRequest, pr_kind: PRKind) -> bool {
+    pr_kind == PRKind::All
+        || (pr_kind == PRKind::Draft && pr.draft)
+        || (pr_kind == PRKind::Open && !pr.draft)
+}
+
+fn create_branch_for_pull_request(
+    repo_path: &Path,
+    pr: &ChangeRequest,
+    head_ref: &str,
+) -> Result<(String, AddKind)> {
+    let branch_name = &pr.source_branch;
+    let repo = open_repo(&repo_path);
+
+    // The head ref points at the PR commit even when a branch of the same name exists on origin
+    let branch = match repo.refname_to_id(head_ref) {
+        Ok(head) => BranchInfo {
+            name: branch_name.to_string(),
+            head: head.to_string(),
+        },
+        Err(_) if !pr.from_fork => get_branch(&repo, branch_name, BranchType::Remote)
+            .with_context(|| format!("Neither `{}` nor `{}` was fetched", head_ref, branch_name))?,
+        Err(_) => bail!(
+            "`{}` was not fetched, the source branch `{}` lives in a fork",
+            head_ref,
+            branch_name
+        ),
+    };
+
+    let (command, add_kind) = if repo.is_bare() || repo.is_worktree() {
+        add_worktree_from_branch(&repo, branch_name, &Some(branch))?
+    } else {
+        add_branch_from_reference(&repo, &branch)?
+    };
+
+    if add_kind == AddKind::Added {
+        info!(
+            "Added workspace for PR #{} {}: branch: {}, url: {}",
+            pr.number,
+            pr.title,
+            branch_name,
+            pr.url.as_deref().unwrap_or("URL not available")
+        );
+    }
+
+    Ok((command, add_kind))
+}
+
+fn filter_prs(prs: Vec<ChangeRequest>, repo: &Repository, pr_kind: PRKind) -> Vec<ChangeRequest> {
+    prs.into_iter()
+        .filter(|pr| {
+            let branch_name = &pr.source_branch;
+            matches_pr_kind(pr, pr_kind)
+                && !worktree_exists_by_branch_name(repo, branch_name).unwrap_or(false)
+        })
+        .collect()
+}
+
+async fn select_prs(
+    prs: Vec<ChangeRequest>,
+    pr_selection: PrSelection,
+) -> Result<Vec<ChangeRequest>> {
+    match pr_selection {
+        PrSelection::All => Ok(prs),
+        PrSelection::Multiple | PrSelection::Single => {
+            let items = prs
+                .iter()
+                .map(|pr| pr.source_branch.clone())
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            let out = get_fuzzy_options(
+                None,
+                pr_selection == PrSelection::Multiple,
+                String::from("PR branch"),
+                items,
+            )
+            .await;
+
+            match out {
+                Some(out) => {
+                    let selected_prs = out
+                        .selected_items
+                        .iter()
+                        .map(|selected_item| {
+                            (**selected_item)
+                                .as_any()
+                                .downcast_ref::<String>()
+                                .unwrap()
+                                .to_owned()
+                        })
+                        .collect::<Vec<String>>();
+
+                    handle_final_key(&out, &selected_prs)?;
+
+                    let prs = prs
+                        .into_iter()
+                        .filter(|pr| {
+                            selected_prs
+                                .iter()
+                                .any(|selected_pr| *selected_pr == pr.source_branch)
+                        })
+                        .collect();
+
+                    Ok(prs)
+                }
+                None => bail!("No available PRs to select"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use git2::{Oid, Signature};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// A canned API response for requests whose request line starts with `request`
+    struct Route {
+        request: String,
+        headers: String,
+        body: String,
+    }
+
+    impl Route {
+        fn new(request: &str, body: &str) -> Self {
+            Route {
+                request: request.to_string(),
+                headers: String::new(),
+                body: body.to_string(),
+            }
+        }
+
+        fn with_header(mut self, name: &str, value: &str) -> Self {
+            self.headers.push_str(&format!("{}: {}\r\n", name, value));
+            self
+        }
+    }
+
+    /// A forge API answering with the first matching route and 404 otherwise, `routes` gets the
+    /// base URL for links to further pages. Returns the base URL
+    async fn serve_api<F>(routes: F) -> String
+    where
+        F: FnOnce(&str) -> Vec<Route>,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let routes = routes(&base_url);
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap_or_default();
+                let request = String::from_utf8_lossy(&request[..read]);
+
+                let route = routes
+                    .iter()
+                    .find(|route| request.starts_with(&route.request));
+                let (status, headers, body) = match route {
+                    Some(route) => ("200 OK", route.headers.as_str(), route.body.as_str()),
+                    None => ("404 Not Found", "", "{}"),
+                };
+
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    headers,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        base_url
+    }
+
+    fn commit(repo: &Repository, parents: &[Oid], message: &str) -> Oid {
+        let signature = Signature::now("test", "test@example.com").unwrap();
+        let tree_id = repo.treebuilder(None).unwrap().write().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parents = parents
+            .iter()
+            .map(|parent| repo.find_commit(*parent).unwrap())
+            .collect::<Vec<_>>();
+
+        repo.commit(
+            None,
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents.iter().collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    fn get_test_root(forge: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!("worktree-cli-pr-{}-{}", forge, process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        root
+    }
+
+    fn set_references(repo: &Repository, references: &[(&str, Oid)]) {
+        for (name, oid) in references {
+            repo.reference(name, *oid, true, "").unwrap();
+        }
+    }
+
+    /// Bare clone of origin using `forge` at `api_url`. The forge is derived from the configured
+    /// remote URL, which git rewrites to the local origin
+    fn create_repo(root: &Path, forge: &str, api_url: &str) -> Repository {
+        let repo = Repository::init_bare(root.join("proj/.git")).unwrap();
+        repo.remote("origin", "https://forge.test/owner/repo.git")
+            .unwrap();
+
+        let mut config = repo.config().unwrap();
+        config
+            .set_str(
+                &format!("url.{}/.insteadOf", root.display()),
+                "https://forge.test/",
+            )
+            .unwrap();
+        config.set_str("worktree-cli.forge", forge).unwrap();
+        config.set_str("worktree-cli.forge-url", api_url).unwrap();
+
+        repo
+    }
+
+    fn fetch_settings() -> FetchSettings {
+        FetchSettings {
+            quiet: true,
+            ..Default::default()
+        }
+    }
+
+    async fn add_all(repo: &Repository, pr_numbers: Vec<u64>) -> Vec<PrResult> {
+        let pr_results = add_workspace_by_pull_requests(
+            repo,
+            ChangeRequestState::Open,
+            PRKind::All,
+            PrSelection::All,
+            pr_numbers,
+            fetch_settings(),
+            2,
+        )
+        .await
+        .unwrap();
+
+        for pr_result in &pr_results {
+            assert_eq!(
+                pr_result.status,
+                PrResultStatus::Added,
+                "#{}: {:?}",
+                pr_result.number,
+                pr_result.error
+            );
+        }
+
+        pr_results
+    }
+
+    fn get_worktree_head(repo: &Repository, worktree_name: &str) -> Option<Oid> {
+        let worktree = repo.find_worktree(worktree_name).unwrap();
+        let worktree_repo = Repository::open_from_worktree(&worktree).unwrap();
+        let head = worktree_repo.head().unwrap().target();
+
+        head
+    }
+
+    #[tokio::test]
+    async fn fork_pr_is_added_at_its_head_ref_not_at_the_origin_branch_of_the_same_name() {
+        let root = get_test_root("gitea");
+
+        // Origin has a `patch-1` of its own, PR #7 comes from a fork's `patch-1`
+        let origin = Repository::init_bare(root.join("owner/repo.git")).unwrap();
+        let base = commit(&origin, &[], "base");
+        let pr_head = commit(&origin, &[base], "change from the fork");
+        set_references(
+            &origin,
+            &[
+                ("refs/heads/main", base),
+                ("refs/heads/patch-1", base),
+                ("refs/pull/7/head", pr_head),
+            ],
+        );
+
+        let api_url = serve_api(|_| {
+            vec![Route::new(
+                "GET /api/v1/repos/owner/repo/pulls/7 ",
+                r#"{"number":7,"title":"Fix from a fork","state":"open","merged":false,"html_url":null,"head":{"ref":"patch-1","repo_id":2},"base":{"ref":"main","repo_id":1}}"#,
+            )]
+        })
+        .await;
+
+        let repo = create_repo(&root, "gitea", &api_url);
+
+        // A fetched `origin/patch-1` must not win over the head ref of the PR
+        fetch_branches(&repo, &["patch-1"], &[], &fetch_settings()).unwrap();
+
+        let pr_results = add_all(&repo, vec![7]).await;
+
+        assert_eq!(pr_results.len(), 1);
+        assert_eq!(get_worktree_head(&repo, "patch-1"), Some(pr_head));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn gitea_prs_are_listed_past_pages_shortened_by_the_server() {
+        let root = get_test_root("gitea-pages");
+
+        let origin = Repository::init_bare(root.join("owner/repo.git")).unwrap();
+        let base = commit(&origin, &[], "base");
+        let fork_head = commit(&origin, &[base], "change from a fork");
+        let branch_head = commit(&origin, &[base], "change on origin");
+        set_references(
+            &origin,
+            &[
+                ("refs/heads/main", base),
+                ("refs/heads/feature", branch_head),
+                ("refs/pull/7/head", fork_head),
+                ("refs/pull/8/head", branch_head),
+            ],
+        );
+
+        let pull_request = |number: u64, branch: &str, head_repo_id: u64| {
+            format!(
+                r#"{{"number":{number},"title":"PR {number}","state":"open","merged":false,"html_url":null,"head":{{"ref":"{branch}","repo_id":{head_repo_id}}},"base":{{"ref":"main","repo_id":1}}}}"#
+            )
+        };
+
+        // A server with a lower maximum answers with fewer items than asked for on every page
+        let api_url = serve_api(|_| {
+            let list = "GET /api/v1/repos/owner/repo/pulls?state=open&limit=50";
+
+            vec![
+                Route::new(
+                    &format!("{}&page=1 ", list),
+                    &format!("[{}]", pull_request(7, "patch-1", 2)),
+                ),
+                Route::new(
+                    &format!("{}&page=2 ", list),
+                    &format!("[{}]", pull_request(8, "feature", 1)),
+                ),
+                Route::new(&format!("{}&page=3 ", list), "[]"),
+            ]
+        })
+        .await;
+
+        let repo = create_repo(&root, "gitea", &api_url);
+
+        let pr_results = add_all(&repo, Vec::new()).await;
+
+        assert_eq!(pr_results.len(), 2);
+        assert_eq!(get_worktree_head(&repo, "patch-1"), Some(fork_head));
+        assert_eq!(get_worktree_head(&repo, "feature"), Some(branch_head));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn github_prs_are_listed_from_all_pages_of_the_enterprise_api() {
+        let root = get_test_root("github");
+
+        let origin = Repository::init_bare(root.join("owner/repo.git")).unwrap();
+        let base = commit(&origin, &[], "base");
+        let fork_head = commit(&origin, &[base], "change from a fork");
+        let branch_head = commit(&origin, &[base], "change on origin");
+        set_references(
+            &origin,
+            &[
+                ("refs/heads/main", base),
+                ("refs/heads/patch-1", base),
+                ("refs/heads/feature", branch_head),
+                ("refs/pull/7/head", fork_head),
+                ("refs/pull/8/head", branch_head),
+            ],
+        );
+
+        let pull_request = |number: u64, branch: &str, head_repo_id: u64| {
+            format!(
+                r#"{{"url":"https://forge.test/pulls/{number}","id":{number},"number":{number},"title":"PR {number}","state":"open","head":{{"ref":"{branch}","sha":"0","repo":{{"id":{head_repo_id},"name":"repo","url":"https://forge.test/repos/{head_repo_id}"}}}},"base":{{"ref":"main","sha":"0","repo":{{"id":1,"name":"repo","url":"https://forge.test/repos/1"}}}}}}"#
+            )
+        };
+
+        // GitHub Enterprise serves the API under `/api/v3`, further pages are linked
+        let api_url = serve_api(|base_url| {
+            vec![
+                Route::new(
+                    "GET /api/v3/repositories/1/pulls?page=2 ",
+                    &format!("[{}]", pull_request(8, "feature", 1)),
+                ),
+                Route::new(
+                    "GET /api/v3/repos/owner/repo/pulls?",
+                    &format!("[{}]", pull_request(7, "patch-1", 2)),
+                )
+                .with_header(
+                    "Link",
+                    &format!(
+                        r#"<{}/api/v3/repositories/1/pulls?page=2>; rel="next""#,
+                        base_url
+                    ),
+                ),
+            ]
+        })
+        .await;
+
+        let repo = create_repo(&root, "github", &api_url);
+
+        let pr_results = add_all(&repo, Vec::new()).await;
+
+        assert_eq!(pr_results.len(), 2);
+        assert_eq!(get_worktree_head(&repo, "patch-1"), Some(fork_head));
+        assert_eq!(get_worktree_head(&repo, "feature"), Some(branch_head));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn gitlab_merge_requests_are_listed_from_all_pages() {
+        let root = get_test_root("gitlab");
+
+        let origin = Repository::init_bare(root.join("owner/repo.git")).unwrap();
+        let base = commit(&origin, &[], "base");
+        let fork_head = commit(&origin, &[base], "change from a fork");
+        let branch_head = commit(&origin, &[base], "change on origin");
+        set_references(
+            &origin,
+            &[
+                ("refs/heads/main", base),
+                ("refs/heads/patch-1", base),
+                ("refs/heads/feature", branch_head),
+                ("refs/merge-requests/7/head", fork_head),
+                ("refs/merge-requests/8/head", branch_head),
+            ],
+        );
+
+        let merge_request = |number: u64, branch: &str, source_project_id: u64| {
+            format!(
+                r#"{{"iid":{number},"title":"MR {number}","source_branch":"{branch}","source_project_id":{source_project_id},"target_project_id":1,"state":"opened","web_url":null}}"#
+            )
+        };
+
+        // Nested groups are part of the project id, so the path separator is encoded
+        let api_url = serve_api(|_| {
+            let list = "GET /api/v4/projects/owner%2Frepo/merge_requests?state=opened&per_page=100";
+
+            vec![
+                Route::new(
+                    &format!("{}&page=1 ", list),
+                    &format!("[{}]", merge_request(7, "patch-1", 2)),
+                )
+                .with_header("X-Next-Page", "2"),
+                Route::new(
+                    &format!("{}&page=2 ", list),
+                    &format!("[{}]", merge_request(8, "feature", 1)),
+                )
+                .with_header("X-Next-Page", ""),
+            ]
+        })
+        .await;
+
+        let repo = create_repo(&root, "gitlab", &api_url);
+
+        let pr_results = add_all(&repo, Vec::new()).await;
+
+        assert_eq!(pr_results.len(), 2);
+        assert_eq!(get_worktree_head(&repo, "patch-1"), Some(fork_head));
+        assert_eq!(get_worktree_head(&repo, "feature"), Some(branch_head));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RemoteUrl {
+    pub scheme: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+impl RemoteUrl {
+    /// Base URL of the forge web interface, SSH remotes fall back to HTTPS on the same host
+    pub(crate) fn web_url(&self) -> String {
+        match (self.scheme.as_deref(), self.port) {
+            (Some(scheme @ ("http" | "https")), Some(port)) => {
+                format!("{}://{}:{}", scheme, self.host, port)
+            }
+            (Some(scheme @ ("http" | "https")), None) => format!("{}://{}", scheme, self.host),
+            _ => format!("https://{}", self.host),
+        }
+    }
+
+    /// Owner (user, organization or nested groups) and repository name
+    pub(crate) fn owner_and_repo(&self) -> Result<(&str, &str)> {
+        self.path
+            .rsplit_once('/')
+            .filter(|(owner, repo)| !owner.is_empty() && !repo.is_empty())
+            .ok_or_else(|| anyhow!("Failed to get owner and repository from `{}`", self.path))
+    }
+}
+
+// Helper function to parse `scheme://[user@]host[:port]/path` and scp-like `[user@]host:path` URLs
+pub(crate) fn parse_remote_url(url: &str) -> Result<RemoteUrl> {
+    let re = Regex::new(
+        r"^(?:(?P<scheme>[a-z][a-z0-9+.-]*)://(?:[^@/]+@)?(?P<host>[^:/]+)(?::(?P<port>\d+))?/|(?:[^@/]+@)?(?P<scp_host>[^:/]+):)(?P<path>.+?)(?:\.git)?/?$",
+    )?;
+
+    let caps = re
+        .captures(url)
+        .ok_or_else(|| anyhow!("Invalid remote URL `{}`", url))?;
+    let host = caps
+        .name("host")
+        .or_else(|| caps.name("scp_host"))
+        .ok_or_else(|| anyhow!("Host not found"))?
+        .as_str();
+    let path = caps
+        .name("path")
+        .ok_or_else(|| anyhow!("Repository path not found"))?
+        .as_str();
+    let port = caps
+        .name("port")
+        .map(|port| port.as_str().parse::<u16>())
+        .transpose()?;
+
+    Ok(RemoteUrl {
+        scheme: caps
+            .name("scheme")
+            .map(|scheme| scheme.as_str().to_string()),
+        host: host.to_string(),
+        port,
+        path: path.trim_start_matches('/').to_string(),
+    })
+}
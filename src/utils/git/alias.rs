@@ -0,0 +1,119 @@
+use anyhow::{bail, Result};
+use git2::{Error, Repository};
+
+use crate::utils::git::worktree::worktree_exists_by_name;
+
+// Stored as `worktree-alias.<alias>.worktree`, subsections keep the case of the alias
+const ALIAS_SECTION: &str = "worktree-alias";
+
+fn get_alias_key(alias: &str) -> String {
+    format!("{}.{}.worktree", ALIAS_SECTION, alias)
+}
+
+/// All aliases with the worktree they point to, sorted by alias
+pub(crate) fn get_aliases(repo: &Repository) -> Result<Vec<(String, String)>, Error> {
+    let config = repo.config()?.snapshot()?;
+    let mut aliases = Vec::new();
+
+    let mut entries = config.entries(Some(&format!(r"^{}\..*\.worktree$", ALIAS_SECTION)))?;
+
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+
+        let alias = entry
+            .name()
+            .and_then(|name| name.strip_prefix(&format!("{}.", ALIAS_SECTION)))
+            .and_then(|name| name.strip_suffix(".worktree"));
+
+        if let (Some(alias), Some(worktree_name)) = (alias, entry.value()) {
+            aliases.push((alias.to_string(), worktree_name.to_string()));
+        }
+    }
+
+    aliases.sort();
+
+    Ok(aliases)
+}
+
+pub(crate) fn get_aliases_of(repo: &Repository, worktree_name: &str) -> Vec<String> {
+    get_aliases(repo)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, target)| target == worktree_name)
+        .map(|(alias, _)| alias)
+        .collect()
+}
+
+/// Returns the worktree `name` is an alias of, worktree names take precedence
+pub(crate) fn resolve_worktree_alias(repo: &Repository, name: &str) -> String {
+    if worktree_exists_by_name(repo, &name).unwrap_or(false) {
+        return name.to_string();
+    }
+
+    repo.config()
+        .and_then(|config| config.get_string(&get_alias_key(name)))
+        .unwrap_or_else(|_| name.to_string())
+}
+
+pub(crate) fn add_alias(repo: &Repository, alias: &str, worktree_name: &str) -> Result<()> {
+    if alias.is_empty() || alias.contains(char::is_whitespace) {
+        bail!("Alias `{}` must not be empty or contain whitespace", alias);
+    }
+
+    if !worktree_exists_by_name(repo, &worktree_name)? {
+        bail!("Worktree `{}` not found", worktree_name);
+    }
+
+    if worktree_exists_by_name(repo, &alias)? {
+        bail!("`{}` is already the name of a worktree", alias);
+    }
+
+    let mut config = repo.config()?;
+
+    if let Ok(target) = config.get_string(&get_alias_key(alias)) {
+        bail!(
+            "Alias `{}` already points to `{}`, remove it first",
+            alias,
+            target
+        );
+    }
+
+    Ok(config.set_str(&get_alias_key(alias), worktree_name)?)
+}
+
+/// Returns the worktree the alias pointed to
+pub(crate) fn remove_alias(repo: &Repository, alias: &str) -> Result<String> {
+    let mut config = repo.config()?;
+
+    let Ok(worktree_name) = config.get_string(&get_alias_key(alias)) else {
+        bail!("Alias `{}` not found", alias);
+    };
+
+    config.remove(&get_alias_key(alias))?;
+
+    Ok(worktree_name)
+}
+
+pub(crate) fn remove_aliases_of(repo: &Repository, worktree_name: &str) -> Result<(), Error> {
+    let mut config = repo.config()?;
+
+    for alias in get_aliases_of(repo, worktree_name) {
+        config.remove(&get_alias_key(&alias))?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn rename_aliases_of(
+    repo: &Repository,
+    old_worktree_name: &str,
+    new_worktree_name: &str,
+) -> Result<(), Error> {
+    let mut config = repo.config()?;
+
+    for alias in get_aliases_of(repo, old_worktree_name) {
+        config.set_str(&get_alias_key(&alias), new_worktree_name)?;
+    }
+
+    Ok(())
+}
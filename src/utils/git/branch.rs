@@ -1,12 +1,8 @@
-use std::ffi::OsStr;
+use std::{collections::HashSet, ffi::OsStr, sync::Mutex};
 
 use git2::{BranchType, Error, Oid, Repository};
-use indexmap::IndexMap;
 
-use super::{
-    commit::get_commit_time,
-    worktree::{get_worktree_names, AddKind},
-};
+use super::{commit::get_commit_time, worktree::AddKind};
 
 pub(crate) fn get_branches(repo: &Repository, branch_type: BranchType) -> Vec<BranchInfo> {
     let branches = repo
@@ -16,11 +12,14 @@ pub(crate) fn get_branches(repo: &Repository, branch_type: BranchType) -> Vec<Br
     let mut local_branches: Vec<(BranchInfo, i64)> = branches
         .filter_map(|branch| {
             branch.ok().and_then(|(branch, _)| {
+                // Symbolic refs like `origin/HEAD` have no target of their own
+                let head = branch.get().target()?;
+
                 branch.name().ok().flatten().map(|name| {
                     (
                         BranchInfo {
                             name: name.to_string(),
-                            head: branch.get().target().unwrap().to_string(),
+                            head: head.to_string(),
                         },
                         get_commit_time(repo, &branch),
                     )
@@ -33,40 +32,19 @@ pub(crate) fn get_branches(repo: &Repository, branch_type: BranchType) -> Vec<Br
 
     local_branches.into_iter().map(|(name, _)| name).collect()
 }
-pub(crate) fn get_worktree_branches(repo: &Repository) -> Result<IndexMap<String, String>, Error> {
-    let worktree_names = get_worktree_names(repo);
-    let mut worktree_branches = IndexMap::new();
-
-    for worktree_name in worktree_names.iter() {
-        if let Ok(worktree_branch) = get_worktree_branch(repo, worktree_name) {
-            worktree_branches.insert(worktree_name.to_string(), worktree_branch);
-        }
-    }
-
-    Ok(worktree_branches)
-}
 
-pub(crate) fn get_worktree_branch(repo: &Repository, worktree_name: &str) -> Result<String, Error> {
-    let worktree_path = repo.path().parent().unwrap().join(worktree_name);
-    let worktree_repo = Repository::open(worktree_path)?;
-    let head = worktree_repo.head()?;
-    let branch_name = head
-        .shorthand()
-        .ok_or_else(|| Error::from_str("Failed to get branch name"))?;
-    Ok(branch_name.to_string())
-}
-
-pub(crate) fn get_worktree_branch_name(
-    repo: &Repository,
-    worktree_name: &str,
-) -> Result<String, Error> {
-    let worktree_path = repo.path().parent().unwrap().join(worktree_name);
-    let worktree_repo = Repository::open(worktree_path)?;
-    let head = worktree_repo.head()?;
-    let branch_name = head
-        .shorthand()
-        .ok_or_else(|| Error::from_str("Failed to get branch name"))?;
-    Ok(branch_name.to_string())
+/// Branches on origin without a local branch of the same name, most recent first
+pub(crate) fn get_remote_only_branch_names(repo: &Repository) -> Vec<String> {
+    let local_branch_names = get_branches(repo, BranchType::Local)
+        .into_iter()
+        .map(|branch| branch.name)
+        .collect::<HashSet<String>>();
+
+    get_branches(repo, BranchType::Remote)
+        .into_iter()
+        .filter_map(|branch| branch.name.strip_prefix("origin/").map(str::to_string))
+        .filter(|branch_name| !local_branch_names.contains(branch_name))
+        .collect()
 }
 
 pub(crate) fn branch_exists_by_name<S>(
@@ -153,10 +131,18 @@ pub(crate) fn _get_remote_branch_reference<'a>(
     repo.find_reference(&remote_branch_ref)
 }
 
+// Worktrees for PRs are created from several tasks at once, each with its own `Repository`,
+// so ref updates are serialized to avoid racing on ref lock files
+static REF_UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
 pub(crate) fn get_local_branch_reference<'a>(
     repo: &'a Repository,
     branch: &'a BranchInfo,
 ) -> Result<git2::Reference<'a>, git2::Error> {
+    let _lock = REF_UPDATE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
     let local_branch_ref = format!("refs/heads/{}", branch.name);
     let reference = repo.find_reference(&local_branch_ref);
 
@@ -0,0 +1,199 @@
+use anyhow::{bail, Context, Result};
+use git2::{
+    build::CheckoutBuilder, CheckoutNotificationType, ErrorCode, Repository, RepositoryState,
+    StashApplyOptions,
+};
+
+use super::{
+    is_branch_clear,
+    rebase::{get_conflicted_paths, UpdateOutcome},
+    sync::get_head_branch_name,
+};
+
+/// Re-applies the newest stash, which is only dropped when it applied without conflicts
+fn apply_autostash(repo: &mut Repository) -> Result<UpdateOutcome> {
+    let mut apply_options = StashApplyOptions::new();
+    apply_options.reinstantiate_index();
+
+    let result = match repo.stash_apply(0, Some(&mut apply_options)) {
+        // Staged changes which conflict are applied to the working directory only
+        Err(e) if e.code() == ErrorCode::MergeConflict => repo.stash_apply(0, None),
+        result => result,
+    };
+
+    match result {
+        Ok(()) => {
+            let index = repo.index()?;
+
+            if index.has_conflicts() {
+                return Ok(UpdateOutcome::Conflicted(get_conflicted_paths(&index)));
+            }
+
+            repo.stash_drop(0)?;
+
+            Ok(UpdateOutcome::Updated)
+        }
+        Err(e) if e.code() == ErrorCode::Conflict || e.code() == ErrorCode::MergeConflict => {
+            Ok(UpdateOutcome::Conflicted(Vec::new()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Switches the working directory to the local branch `branch_name`. Uncommitted changes are
+/// carried over when the branch doesn't touch the same files, with `autostash` they are
+/// stashed and re-applied on top of the branch instead
+pub(crate) fn checkout_branch(
+    repo: &mut Repository,
+    branch_name: &str,
+    autostash: bool,
+) -> Result<UpdateOutcome> {
+    if repo.state() != RepositoryState::Clean {
+        bail!("Cannot switch branches, {:?} is in progress", repo.state());
+    }
+
+    if get_head_branch_name(repo).as_deref() == Some(branch_name) {
+        bail!("Already on `{}`", branch_name);
+    }
+
+    let refname = format!("refs/heads/{}", branch_name);
+    let commit_id = repo
+        .find_reference(&refname)
+        .with_context(|| format!("Branch `{}` not found", branch_name))?
+        .peel_to_commit()?
+        .id();
+
+    let stashed = autostash && !is_branch_clear(repo);
+
+    if stashed {
+        let signature = repo.signature()?;
+        let message = format!("autostash before switching to {}", branch_name);
+
+        repo.stash_save(&signature, &message, None)?;
+    }
+
+    let mut conflicts = Vec::new();
+
+    let result = {
+        let mut checkout = CheckoutBuilder::new();
+        checkout
+            .safe()
+            .notify_on(CheckoutNotificationType::CONFLICT)
+            .notify(|_, path, _, _, _| {
+                if let Some(path) = path {
+                    conflicts.push(path.display().to_string());
+                }
+
+                true
+            });
+
+        let commit = repo.find_commit(commit_id)?;
+        repo.checkout_tree(commit.as_object(), Some(&mut checkout))
+    };
+
+    if let Err(e) = result {
+        if stashed {
+            repo.stash_pop(0, None)?;
+        }
+
+        if conflicts.is_empty() {
+            return Err(e.into());
+        }
+
+        bail!(
+            "Local changes to {} would be overwritten by switching to `{}`, commit them or use --autostash",
+            conflicts.join(", "),
+            branch_name
+        );
+    }
+
+    repo.set_head(&refname)?;
+
+    if !stashed {
+        return Ok(UpdateOutcome::Updated);
+    }
+
+    apply_autostash(repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::utils::git::test_utils::{commit_file, get_test_dir, init_repo};
+
+    use super::*;
+
+    /// Branch `feat` with a commit adding `other`, while `main` stays checked out
+    fn add_feature_branch(repo: &Repository) {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feat", &head, false).unwrap();
+
+        repo.set_head("refs/heads/feat").unwrap();
+        commit_file(repo, "other", "feat\n", "Add other");
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force().remove_untracked(true)))
+            .unwrap();
+    }
+
+    fn count_stashes(repo: &mut Repository) -> usize {
+        let mut count = 0;
+        repo.stash_foreach(|_, _, _| {
+            count += 1;
+            true
+        })
+        .unwrap();
+
+        count
+    }
+
+    #[test]
+    fn autostash_carries_uncommitted_changes_to_the_branch() {
+        let root = get_test_dir("checkout-autostash");
+        let mut repo = init_repo(&root);
+        add_feature_branch(&repo);
+        fs::write(root.join("repo/file"), "dirty\n").unwrap();
+
+        let outcome = checkout_branch(&mut repo, "feat", true).unwrap();
+
+        assert!(matches!(outcome, UpdateOutcome::Updated));
+        assert_eq!(get_head_branch_name(&repo).as_deref(), Some("feat"));
+        assert_eq!(
+            fs::read_to_string(root.join("repo/file")).unwrap(),
+            "dirty\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("repo/other")).unwrap(),
+            "feat\n"
+        );
+        assert_eq!(count_stashes(&mut repo), 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn failed_checkout_gives_the_autostash_back() {
+        let root = get_test_dir("checkout-autostash-failed");
+        let mut repo = init_repo(&root);
+        add_feature_branch(&repo);
+        fs::write(root.join("repo/file"), "dirty\n").unwrap();
+        // Untracked files aren't stashed, so this one blocks the checkout of `feat`
+        fs::write(root.join("repo/other"), "untracked\n").unwrap();
+
+        let error = checkout_branch(&mut repo, "feat", true).unwrap_err();
+
+        assert!(error.to_string().contains("Local changes to other"));
+        assert_eq!(get_head_branch_name(&repo).as_deref(), Some("main"));
+        assert_eq!(
+            fs::read_to_string(root.join("repo/file")).unwrap(),
+            "dirty\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("repo/other")).unwrap(),
+            "untracked\n"
+        );
+        assert_eq!(count_stashes(&mut repo), 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
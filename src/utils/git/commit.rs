@@ -1,6 +1,4 @@
-use git2::{Branch, BranchType, Repository};
-
-use super::branch::get_worktree_branch_name;
+use git2::{Branch, Error, Repository};
 
 pub(crate) fn get_commit_time(repo: &Repository, branch: &Branch) -> i64 {
     let oid = branch.get().target().expect("Failed to get target OID");
@@ -8,18 +6,29 @@ pub(crate) fn get_commit_time(repo: &Repository, branch: &Branch) -> i64 {
     commit.time().seconds()
 }
 
-pub(crate) fn get_worktree_commit_time(repo: &Repository, worktree_name: &str) -> Option<i64> {
-    let branch_name = get_worktree_branch_name(repo, worktree_name).ok()?;
+/// One line per commit reachable from the worktree HEAD, newest first
+pub(crate) fn get_worktree_log(
+    repo: &Repository,
+    worktree_name: &str,
+    limit: usize,
+) -> Result<Vec<String>, Error> {
+    let worktree = repo.find_worktree(worktree_name)?;
+    let worktree_repo = Repository::open_from_worktree(&worktree)?;
+
+    let mut revwalk = worktree_repo.revwalk()?;
+    revwalk.push_head()?;
+
+    revwalk
+        .take(limit)
+        .map(|oid| {
+            let commit = worktree_repo.find_commit(oid?)?;
 
-    match repo.find_branch(&branch_name, BranchType::Local) {
-        Ok(branch) => {
-            let oid = branch.get().target()?;
-            let commit = repo.find_commit(oid).ok()?;
-            Some(commit.time().seconds())
-        }
-        Err(e) => {
-            error!("Failed to find branch {}: {}", branch_name, e);
-            None
-        }
-    }
+            Ok(format!(
+                "{} {} ({})",
+                &commit.id().to_string()[..7],
+                commit.summary().unwrap_or_default(),
+                commit.author().name().unwrap_or_default()
+            ))
+        })
+        .collect()
 }
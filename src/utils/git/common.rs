@@ -1,46 +1,29 @@
 use std::path::PathBuf;
 
-use anyhow::{Error, Ok, Result};
-use git2::{RemoteCallbacks, Repository};
+use anyhow::{Error, Result};
+use git2::Repository;
 
-use crate::utils::github::common::{parse_github_url, GitRepoInfo};
-
-pub(crate) fn get_credentials_callback<'a>() -> RemoteCallbacks<'a> {
-    let mut callbacks = RemoteCallbacks::new();
-
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-        git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
-    });
-
-    callbacks
-}
-
-pub(crate) fn get_repo_info(repo: &Repository) -> Result<GitRepoInfo> {
-    let remote = repo.find_remote("origin")?;
-
-    let url = remote
-        .url()
-        .ok_or_else(|| anyhow::anyhow!("Remote URL not found"))?;
-
-    // Parse the URL to get the owner and repository name
-    let git_repo_info = parse_github_url(url)?;
-
-    Ok(git_repo_info)
-}
+/// Directory new worktrees are created in, the bare repository itself or the one containing
+/// the common directory of a worktree
+pub(crate) fn get_root_repo_path(repo: &Repository) -> Result<PathBuf> {
+    if repo.is_bare() {
+        return Ok(repo.path().to_path_buf());
+    }
 
-fn get_worktree_root_path(repo: &Repository) -> Result<PathBuf> {
-    repo.path()
+    get_common_dir(repo)
         .parent()
-        .and_then(|p| p.parent())
-        .and_then(|p| p.parent())
         .map(|p| p.to_path_buf())
         .ok_or_else(|| Error::msg("Failed to get worktree root path"))
 }
 
-pub(crate) fn get_root_repo_path(repo: &Repository) -> Result<PathBuf> {
+/// Directory shared by all worktrees, i.e. the bare repository or the `.git` directory
+pub(crate) fn get_common_dir(repo: &Repository) -> PathBuf {
     if repo.is_worktree() {
-        return get_worktree_root_path(repo);
+        // Worktrees live in `<common dir>/worktrees/<name>`
+        if let Some(common_dir) = repo.path().parent().and_then(|p| p.parent()) {
+            return common_dir.to_path_buf();
+        }
     }
 
-    Ok(repo.path().to_path_buf())
+    repo.path().to_path_buf()
 }
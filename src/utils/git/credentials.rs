@@ -0,0 +1,208 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    vec::IntoIter,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use git2::{
+    Config, Cred, CredentialType, Error, ErrorClass, ErrorCode, RemoteCallbacks, Repository,
+};
+
+use crate::utils::{
+    forge::remote::parse_remote_url,
+    github::auth::{get_github_env_token, GITHUB_HOST},
+};
+
+const GIT_TOKEN_ENV_VAR: &str = "WORKTREE_CLI_GIT_TOKEN";
+const SSH_KEY_NAMES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+const OPENSSH_KEY_MAGIC: &[u8] = b"openssh-key-v1\0";
+
+/// Tracks which credentials were already handed to libgit2, which calls the callback
+/// again after every rejected attempt, so each source is only tried once
+#[derive(Default)]
+struct CredentialsState {
+    config: Option<Config>,
+    tried_username: bool,
+    tried_credential_helper: bool,
+    ssh_keys: Option<IntoIter<PathBuf>>,
+    tried_ssh_agent: bool,
+    tried_env_token: bool,
+    tried_default: bool,
+}
+
+impl CredentialsState {
+    fn next_credentials(
+        &mut self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: CredentialType,
+    ) -> Result<Cred, Error> {
+        if allowed_types.contains(CredentialType::USERNAME) && !self.tried_username {
+            self.tried_username = true;
+            return Cred::username(username_from_url.unwrap_or("git"));
+        }
+
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && !self.tried_credential_helper
+        {
+            self.tried_credential_helper = true;
+
+            if let Some(cred) = self
+                .config
+                .as_ref()
+                .and_then(|config| Cred::credential_helper(config, url, username_from_url).ok())
+            {
+                debug!("Trying credentials from git credential helper for {}", url);
+                return Ok(cred);
+            }
+        }
+
+        if allowed_types.contains(CredentialType::SSH_KEY) {
+            let username = username_from_url.unwrap_or("git");
+            let ssh_keys = self
+                .ssh_keys
+                .get_or_insert_with(|| find_ssh_keys().into_iter());
+
+            for private_key in ssh_keys.by_ref() {
+                if let Some(cred) = get_ssh_key_credentials(username, &private_key) {
+                    debug!("Trying SSH key {}", private_key.display());
+                    return Ok(cred);
+                }
+            }
+
+            if !self.tried_ssh_agent {
+                self.tried_ssh_agent = true;
+                debug!("Trying SSH agent");
+                return Cred::ssh_key_from_agent(username);
+            }
+        }
+
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) && !self.tried_env_token {
+            self.tried_env_token = true;
+
+            if let Some(token) = get_env_token(url) {
+                debug!("Trying token from environment for {}", url);
+                return Cred::userpass_plaintext(
+                    username_from_url.unwrap_or("x-access-token"),
+                    &token,
+                );
+            }
+        }
+
+        if allowed_types.contains(CredentialType::DEFAULT) && !self.tried_default {
+            self.tried_default = true;
+            return Cred::default();
+        }
+
+        // Marked as an authentication error, so it is not retried
+        Err(Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Callback,
+            format!(
+                "Failed to authenticate to {}, no more credentials to try",
+                url
+            ),
+        ))
+    }
+}
+
+fn find_ssh_keys() -> Vec<PathBuf> {
+    let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) else {
+        return Vec::new();
+    };
+
+    SSH_KEY_NAMES
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|private_key| private_key.is_file())
+        .collect()
+}
+
+fn is_encrypted_ssh_key(private_key: &Path) -> bool {
+    let Ok(contents) = fs::read_to_string(private_key) else {
+        return false;
+    };
+
+    // PEM and PKCS#8 keys mark encryption in their headers
+    if contents.contains("ENCRYPTED") {
+        return true;
+    }
+
+    // OpenSSH keys store the cipher name right after the magic bytes
+    let body = contents
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<String>();
+
+    let Ok(decoded) = STANDARD.decode(body) else {
+        return false;
+    };
+
+    let Some(rest) = decoded.strip_prefix(OPENSSH_KEY_MAGIC) else {
+        return false;
+    };
+
+    let Some((len, rest)) = rest.split_first_chunk::<4>() else {
+        return false;
+    };
+
+    rest.get(..u32::from_be_bytes(*len) as usize)
+        .map(|cipher| cipher != b"none")
+        .unwrap_or(false)
+}
+
+fn get_ssh_key_credentials(username: &str, private_key: &Path) -> Option<Cred> {
+    let public_key = private_key.with_extension("pub");
+
+    let passphrase = if is_encrypted_ssh_key(private_key) {
+        // Without a terminal or passphrase the key is left to the SSH agent
+        let passphrase =
+            rpassword::prompt_password(format!("Enter passphrase for {}: ", private_key.display()))
+                .ok()
+                .filter(|passphrase| !passphrase.is_empty())?;
+
+        Some(passphrase)
+    } else {
+        None
+    };
+
+    Cred::ssh_key(
+        username,
+        public_key.is_file().then_some(public_key.as_path()),
+        private_key,
+        passphrase.as_deref(),
+    )
+    .ok()
+}
+
+fn get_env_token(url: &str) -> Option<String> {
+    if let Ok(token) = env::var(GIT_TOKEN_ENV_VAR) {
+        if !token.trim().is_empty() {
+            return Some(token.trim().to_string());
+        }
+    }
+
+    // GitHub tokens are only sent to GitHub
+    match parse_remote_url(url) {
+        Ok(remote) if remote.host == GITHUB_HOST => {
+            get_github_env_token(&remote.host).map(|token| token.token)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn get_credentials_callback<'a>(repo: &Repository) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    let mut state = CredentialsState {
+        config: repo.config().ok(),
+        ..Default::default()
+    };
+
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        state.next_credentials(url, username_from_url, allowed_types)
+    });
+
+    callbacks
+}
@@ -1,26 +1,196 @@
-use git2::{FetchOptions, Repository};
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
-use super::common::get_credentials_callback;
+use git2::{Direction, Error, FetchOptions, Progress, Remote, Repository};
 
-fn get_fetch_options<'a>() -> FetchOptions<'a> {
-    let callbacks = get_credentials_callback();
+use crate::utils::signal::{cancellable, is_cancelled};
+
+use super::credentials::get_credentials_callback;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct FetchSettings {
+    pub quiet: bool,
+    pub skip: bool,
+    pub full: bool,
+    pub depth: Option<u32>,
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.2} {}", size, UNITS[unit])
+}
+
+fn render_progress(stats: &Progress) -> bool {
+    let line = if stats.received_objects() < stats.total_objects() {
+        format!(
+            "Receiving objects: {:3}% ({}/{}), {}",
+            stats.received_objects() * 100 / stats.total_objects(),
+            stats.received_objects(),
+            stats.total_objects(),
+            format_bytes(stats.received_bytes())
+        )
+    } else if stats.total_deltas() > 0 {
+        format!(
+            "Resolving deltas: {:3}% ({}/{})",
+            stats.indexed_deltas() * 100 / stats.total_deltas(),
+            stats.indexed_deltas(),
+            stats.total_deltas()
+        )
+    } else {
+        return false;
+    };
+
+    let mut stderr = io::stderr();
+    let _ = write!(stderr, "\r{}\x1b[K", line);
+    let _ = stderr.flush();
+
+    true
+}
+
+fn get_fetch_options<'a>(
+    repo: &Repository,
+    settings: &FetchSettings,
+    progress_rendered: Arc<AtomicBool>,
+) -> FetchOptions<'a> {
+    let mut callbacks = get_credentials_callback(repo);
+
+    let show_progress = !settings.quiet && io::stderr().is_terminal();
+
+    callbacks.transfer_progress(move |stats| {
+        if show_progress && render_progress(&stats) {
+            progress_rendered.store(true, Ordering::Relaxed);
+        }
+
+        // Returning false aborts the transfer, refs are only updated once it completes
+        !is_cancelled()
+    });
+
+    if show_progress {
+        callbacks.sideband_progress(|data| {
+            let mut stderr = io::stderr();
+            let _ = stderr.write_all(data);
+            let _ = stderr.flush();
+
+            !is_cancelled()
+        });
+    }
 
     let mut fetch_options = FetchOptions::new();
 
     fetch_options.remote_callbacks(callbacks);
 
+    if let Some(depth) = settings.depth {
+        fetch_options.depth(depth as i32);
+    }
+
     fetch_options
 }
 
-pub(crate) fn fetch_all(repo: &Repository) {
-    let mut fetch_options = get_fetch_options();
+fn get_branch_refspec(remote: &Remote, branch_name: &str) -> String {
+    let src = format!("refs/heads/{branch_name}");
+
+    remote
+        .refspecs()
+        .filter(|refspec| refspec.direction() == Direction::Fetch && refspec.src_matches(&src))
+        .find_map(|refspec| {
+            let dst = refspec.transform(&src).ok()?.as_str()?.to_string();
+            let force = if refspec.is_force() { "+" } else { "" };
+
+            Some(format!("{force}{src}:{dst}"))
+        })
+        .unwrap_or_else(|| {
+            format!(
+                "+{src}:refs/remotes/{}/{branch_name}",
+                remote.name().unwrap_or("origin")
+            )
+        })
+}
+
+fn get_configured_refspecs(remote: &Remote) -> Result<Vec<String>, Error> {
+    let refspecs = remote
+        .fetch_refspecs()?
+        .iter()
+        .flatten()
+        .map(|refspec| refspec.to_string())
+        .collect::<Vec<String>>();
+
+    // Bare clones come without a fetch refspec
+    if refspecs.is_empty() {
+        return Ok(vec![String::from("+refs/heads/*:refs/remotes/origin/*")]);
+    }
+
+    Ok(refspecs)
+}
+
+/// Fetches the given branches, or everything the remote is configured to fetch with
+/// `settings.full`, together with extra references (e.g. PR heads) which are stored
+/// under the same name locally. Refs missing on the remote are skipped
+pub(crate) fn fetch_branches<S>(
+    repo: &Repository,
+    branch_names: &[S],
+    references: &[String],
+    settings: &FetchSettings,
+) -> Result<(), Error>
+where
+    S: AsRef<str>,
+{
+    if settings.skip {
+        debug!("Skipping fetch");
+        return Ok(());
+    }
+
+    let mut remote = repo.find_remote("origin")?;
+
+    let branch_refspecs = if settings.full {
+        get_configured_refspecs(&remote)?
+    } else {
+        branch_names
+            .iter()
+            .map(|branch_name| get_branch_refspec(&remote, branch_name.as_ref()))
+            .collect()
+    };
 
-    repo.find_remote("origin")
-        .expect("Failed to find remote")
-        .fetch(
-            &["+refs/heads/*:refs/remotes/origin/*"],
-            Some(&mut fetch_options),
-            None,
+    let refspecs = branch_refspecs
+        .into_iter()
+        .chain(
+            references
+                .iter()
+                .map(|reference| format!("+{reference}:{reference}")),
         )
-        .expect("Failed to fetch");
+        .collect::<Vec<String>>();
+
+    if refspecs.is_empty() {
+        return Ok(());
+    }
+
+    debug!("Fetching {:?}", refspecs);
+
+    let _guard = cancellable();
+    let progress_rendered = Arc::new(AtomicBool::new(false));
+    let mut fetch_options = get_fetch_options(repo, settings, Arc::clone(&progress_rendered));
+
+    let result = remote.fetch(&refspecs, Some(&mut fetch_options), None);
+
+    if progress_rendered.load(Ordering::Relaxed) {
+        eprintln!();
+    }
+
+    match result {
+        Err(_) if is_cancelled() => Err(Error::from_str("Fetch was cancelled")),
+        result => result,
+    }
 }
@@ -2,10 +2,19 @@ use std::path::Path;
 
 use git2::{Repository, StatusOptions};
 
+pub(crate) mod alias;
 pub(crate) mod branch;
+pub(crate) mod checkout;
 pub(crate) mod commit;
 pub(crate) mod common;
+pub(crate) mod credentials;
 pub(crate) mod fetch;
+pub(crate) mod rebase;
+pub(crate) mod repair;
+pub(crate) mod status;
+pub(crate) mod sync;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub(crate) mod worktree;
 
 pub(crate) fn open_repo<P>(repo_path: &P) -> Repository
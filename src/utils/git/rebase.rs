@@ -0,0 +1,331 @@
+use anyhow::Result;
+use git2::{
+    build::CheckoutBuilder, AnnotatedCommit, ErrorCode, Index, Rebase, Repository, ResetType,
+    Signature,
+};
+
+use super::{
+    fetch::{fetch_branches, FetchSettings},
+    sync::{fast_forward, get_head_branch_name, get_skip_reason, SyncResult, SyncStatus},
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum UpdateOutcome {
+    Updated,
+    Conflicted(Vec<String>),
+}
+
+pub(crate) fn get_conflicted_paths(index: &Index) -> Vec<String> {
+    let Ok(conflicts) = index.conflicts() else {
+        return Vec::new();
+    };
+
+    conflicts
+        .flatten()
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect()
+}
+
+fn apply_rebase(
+    repo: &Repository,
+    rebase: &mut Rebase,
+    signature: &Signature,
+) -> Result<UpdateOutcome, git2::Error> {
+    while let Some(operation) = rebase.next() {
+        operation?;
+
+        let index = repo.index()?;
+
+        if index.has_conflicts() {
+            return Ok(UpdateOutcome::Conflicted(get_conflicted_paths(&index)));
+        }
+
+        match rebase.commit(None, signature, None) {
+            // The change is already in upstream, so the commit is dropped
+            Err(e) if e.code() == ErrorCode::Applied => {}
+            result => {
+                result?;
+            }
+        }
+    }
+
+    Ok(UpdateOutcome::Updated)
+}
+
+/// Rebases the checked out branch onto `upstream`, a conflicting rebase is aborted so the
+/// worktree is left as it was
+pub(crate) fn rebase_onto(
+    repo: &Repository,
+    upstream: &AnnotatedCommit,
+) -> Result<UpdateOutcome, git2::Error> {
+    let signature = repo.signature()?;
+    let branch = repo.reference_to_annotated_commit(&repo.head()?)?;
+
+    let mut rebase = repo.rebase(Some(&branch), Some(upstream), None, None)?;
+
+    match apply_rebase(repo, &mut rebase, &signature) {
+        Ok(UpdateOutcome::Updated) => {
+            rebase.finish(Some(&signature))?;
+
+            Ok(UpdateOutcome::Updated)
+        }
+        result => {
+            rebase.abort()?;
+
+            result
+        }
+    }
+}
+
+/// Merges `upstream` into the checked out branch, a conflicting merge is reset so the
+/// worktree is left as it was
+pub(crate) fn merge_into(
+    repo: &Repository,
+    upstream: &AnnotatedCommit,
+) -> Result<UpdateOutcome, git2::Error> {
+    let (analysis, _) = repo.merge_analysis(&[upstream])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(UpdateOutcome::Updated);
+    }
+
+    if analysis.is_fast_forward() {
+        fast_forward(repo, upstream.id())?;
+
+        return Ok(UpdateOutcome::Updated);
+    }
+
+    repo.merge(&[upstream], None, Some(CheckoutBuilder::new().safe()))?;
+
+    let mut index = repo.index()?;
+
+    if index.has_conflicts() {
+        let paths = get_conflicted_paths(&index);
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.reset(head.as_object(), ResetType::Hard, None)?;
+        repo.cleanup_state()?;
+
+        return Ok(UpdateOutcome::Conflicted(paths));
+    }
+
+    let signature = repo.signature()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let upstream = repo.find_commit(upstream.id())?;
+    let message = repo
+        .message()
+        .unwrap_or_else(|_| format!("Merge commit '{}'", upstream.id()));
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message.trim_end(),
+        &tree,
+        &[&head, &upstream],
+    )?;
+    repo.cleanup_state()?;
+
+    Ok(UpdateOutcome::Updated)
+}
+
+/// Keeps the reference name when `spec` names a branch, so merge messages mention it
+fn get_annotated_commit<'a>(repo: &'a Repository, spec: &str) -> Result<AnnotatedCommit<'a>> {
+    let (object, reference) = repo.revparse_ext(spec)?;
+
+    let annotated_commit = match reference {
+        Some(reference) => repo.reference_to_annotated_commit(&reference)?,
+        None => repo.find_annotated_commit(object.peel_to_commit()?.id())?,
+    };
+
+    Ok(annotated_commit)
+}
+
+fn rebase_worktree(
+    repo: &Repository,
+    onto: &str,
+    merge: bool,
+) -> Result<(Option<String>, SyncStatus, String)> {
+    let Some(branch_name) = get_head_branch_name(repo) else {
+        return Ok((None, SyncStatus::Skipped, String::from("detached HEAD")));
+    };
+
+    let branch = Some(branch_name.clone());
+
+    if let Some(reason) = get_skip_reason(repo) {
+        return Ok((branch, SyncStatus::Skipped, reason));
+    }
+
+    let upstream = get_annotated_commit(repo, onto)?;
+    let local = repo.refname_to_id(&format!("refs/heads/{branch_name}"))?;
+    let (ahead, behind) = repo.graph_ahead_behind(local, upstream.id())?;
+
+    if behind == 0 {
+        return Ok((branch, SyncStatus::UpToDate, String::new()));
+    }
+
+    if ahead == 0 {
+        fast_forward(repo, upstream.id())?;
+
+        let detail = format!("fast-forwarded {behind} commit(s)");
+        return Ok((branch, SyncStatus::Updated, detail));
+    }
+
+    let (outcome, action, detail) = if merge {
+        let detail = format!("merged {behind} new commit(s)");
+        (merge_into(repo, &upstream)?, "merge", detail)
+    } else {
+        let detail = format!("rebased {ahead} commit(s) onto {behind} new commit(s)");
+        (rebase_onto(repo, &upstream)?, "rebase", detail)
+    };
+
+    match outcome {
+        UpdateOutcome::Updated => Ok((branch, SyncStatus::Updated, detail)),
+        UpdateOutcome::Conflicted(paths) => {
+            let detail = format!("{action} aborted, conflicts in {}", paths.join(", "));
+            Ok((branch, SyncStatus::Conflicted, detail))
+        }
+    }
+}
+
+/// Rebases (or merges) every given worktree onto `onto` one after another, a conflict only
+/// affects the worktree it happens in
+pub(crate) fn rebase_worktrees(
+    repo: &Repository,
+    worktree_names: Vec<String>,
+    onto: &str,
+    merge: bool,
+    fetch_settings: &FetchSettings,
+) -> Result<Vec<SyncResult>> {
+    let onto_branch = onto.strip_prefix("origin/").unwrap_or(onto);
+    fetch_branches(repo, &[onto_branch], &[], fetch_settings)?;
+
+    // The fetched remote branch wins over a local branch of the same name, which may be stale.
+    // Without a fetch the local branch may be the newer one, so `onto` is taken as it is
+    let remote_ref = format!("refs/remotes/origin/{onto_branch}");
+    let onto_ref = if !fetch_settings.skip && repo.find_reference(&remote_ref).is_ok() {
+        remote_ref
+    } else {
+        // Resolve early, so a typo fails once instead of for every worktree
+        repo.revparse_single(onto)?;
+        onto.to_string()
+    };
+
+    let rebase_results = worktree_names
+        .into_iter()
+        .filter_map(|worktree| {
+            let result = repo
+                .find_worktree(&worktree)
+                .and_then(|worktree| Repository::open_from_worktree(&worktree))
+                .map_err(anyhow::Error::from);
+
+            // The worktree of the base branch itself is left alone
+            if let Ok(worktree_repo) = &result {
+                if get_head_branch_name(worktree_repo).as_deref() == Some(onto_branch) {
+                    return None;
+                }
+            }
+
+            let (branch, status, detail) = result
+                .and_then(|worktree_repo| rebase_worktree(&worktree_repo, &onto_ref, merge))
+                .unwrap_or_else(|e| {
+                    error!("Failed to update worktree `{}`: {:?}", worktree, e);
+
+                    (None, SyncStatus::Failed, format!("{:#}", e))
+                });
+
+            Some(SyncResult {
+                worktree,
+                branch,
+                status,
+                detail,
+            })
+        })
+        .collect();
+
+    Ok(rebase_results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use git2::WorktreeAddOptions;
+
+    use crate::utils::git::test_utils::{commit_file, get_test_dir, init_repo};
+
+    use super::*;
+
+    const NO_FETCH: FetchSettings = FetchSettings {
+        quiet: true,
+        skip: true,
+        full: false,
+        depth: None,
+    };
+
+    #[test]
+    fn without_fetch_the_local_branch_is_used_over_a_stale_remote_one() {
+        let root = get_test_dir("rebase-no-fetch");
+        let repo = init_repo(&root);
+        let first = repo.head().unwrap().target().unwrap();
+
+        let feat = repo
+            .branch("feat", &repo.find_commit(first).unwrap(), false)
+            .unwrap();
+        repo.worktree(
+            "feat",
+            &root.join("feat"),
+            Some(WorktreeAddOptions::new().reference(Some(feat.get()))),
+        )
+        .unwrap();
+
+        let second = commit_file(&repo, "file", "second\n", "Second commit");
+        repo.reference("refs/remotes/origin/main", first, false, "stale")
+            .unwrap();
+
+        let rebase_results =
+            rebase_worktrees(&repo, vec![String::from("feat")], "main", false, &NO_FETCH).unwrap();
+
+        assert_eq!(rebase_results[0].status, SyncStatus::Updated);
+        assert_eq!(repo.refname_to_id("refs/heads/feat").unwrap(), second);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn worktree_of_the_base_branch_is_skipped_for_its_remote_name() {
+        let root = get_test_dir("rebase-skip-base");
+        let repo = init_repo(&root);
+        let first = repo.head().unwrap().target().unwrap();
+
+        let dev = repo
+            .branch("dev", &repo.find_commit(first).unwrap(), false)
+            .unwrap();
+        repo.worktree(
+            "dev",
+            &root.join("dev"),
+            Some(WorktreeAddOptions::new().reference(Some(dev.get()))),
+        )
+        .unwrap();
+
+        let second = commit_file(&repo, "file", "second\n", "Second commit");
+        repo.reference("refs/remotes/origin/dev", second, false, "ahead")
+            .unwrap();
+
+        let rebase_results = rebase_worktrees(
+            &repo,
+            vec![String::from("dev")],
+            "origin/dev",
+            false,
+            &NO_FETCH,
+        )
+        .unwrap();
+
+        assert!(rebase_results.is_empty());
+        assert_eq!(repo.refname_to_id("refs/heads/dev").unwrap(), first);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
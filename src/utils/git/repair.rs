@@ -0,0 +1,412 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use git2::{Repository, WorktreePruneOptions};
+
+use super::{
+    common::{get_common_dir, get_root_repo_path},
+    worktree::{get_lock_reason, write_worktree_links},
+};
+
+// Worktrees are usually next to the repository, so the search doesn't need to go deep
+const SEARCH_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RepairStatus {
+    Ok,
+    Repaired,
+    NeedsRepair,
+    Missing,
+    Pruned,
+    Conflict,
+    Failed,
+}
+
+impl fmt::Display for RepairStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepairStatus::Ok => write!(f, "ok"),
+            RepairStatus::Repaired => write!(f, "repaired"),
+            RepairStatus::NeedsRepair => write!(f, "needs repair"),
+            RepairStatus::Missing => write!(f, "missing"),
+            RepairStatus::Pruned => write!(f, "pruned"),
+            RepairStatus::Conflict => write!(f, "conflict"),
+            RepairStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RepairResult {
+    pub worktree: String,
+    pub path: Option<PathBuf>,
+    pub status: RepairStatus,
+    pub detail: String,
+    /// Whether the worktree was found somewhere else, relinking it then overwrites nothing
+    pub moved: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RepairSettings {
+    pub search_paths: Vec<PathBuf>,
+    pub prune: bool,
+    pub dry_run: bool,
+    /// Only relinks moved worktrees, a `.git` file at the recorded path is reported instead
+    pub moved_only: bool,
+}
+
+fn is_same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.components().eq(b.components()),
+    }
+}
+
+/// Target of a worktree's `.git` file, relative paths are resolved against the worktree
+fn read_git_file(path: &Path) -> Option<PathBuf> {
+    let contents = fs::read_to_string(path).ok()?;
+    let target = Path::new(contents.trim().strip_prefix("gitdir:")?.trim());
+
+    Some(path.parent()?.join(target))
+}
+
+/// `.git` file of the worktree recorded in its metadata
+fn read_admin_gitdir(admin_path: &Path) -> Option<PathBuf> {
+    let contents = fs::read_to_string(admin_path.join("gitdir")).ok()?;
+
+    Some(admin_path.join(contents.trim()))
+}
+
+/// Looks for a directory whose `.git` file still points at the metadata in `admin_path`
+fn find_moved_worktree(search_paths: &[PathBuf], admin_path: &Path) -> Option<PathBuf> {
+    let mut pending = search_paths
+        .iter()
+        .map(|path| (path.clone(), 0))
+        .collect::<Vec<(PathBuf, usize)>>();
+
+    while let Some((dir, depth)) = pending.pop() {
+        let git_file = dir.join(".git");
+
+        if git_file.is_file() {
+            if read_git_file(&git_file).is_some_and(|target| is_same_path(&target, admin_path)) {
+                return Some(dir);
+            }
+
+            continue;
+        }
+
+        if depth >= SEARCH_DEPTH {
+            continue;
+        }
+
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+            if !is_hidden && entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                pending.push((entry.path(), depth + 1));
+            }
+        }
+    }
+
+    None
+}
+
+fn repair_worktree(
+    repo: &Repository,
+    worktree_name: &str,
+    admin_path: &Path,
+    settings: &RepairSettings,
+) -> Result<(Option<PathBuf>, RepairStatus, String, bool)> {
+    let Some(git_file) = read_admin_gitdir(admin_path) else {
+        bail!("`{}` has no gitdir file", admin_path.display());
+    };
+
+    let recorded_path = git_file
+        .parent()
+        .context("Invalid gitdir file")?
+        .to_path_buf();
+    let recorded_git_file = recorded_path.join(".git");
+
+    // Relinking in place only replaces a `.git` file that is unreadable or still meant for this
+    // worktree, e.g. after the repository itself was moved
+    let in_place_problem = match read_git_file(&recorded_git_file) {
+        Some(target) if is_same_path(&target, admin_path) => {
+            return Ok((Some(recorded_path), RepairStatus::Ok, String::new(), false));
+        }
+        Some(target) if !target.exists() && target.file_name() == admin_path.file_name() => {
+            Some(format!("`.git` points to `{}`", target.display()))
+        }
+        None if recorded_git_file.is_file() => Some(String::from("`.git` file is unreadable")),
+        _ => None,
+    };
+
+    let (worktree_path, problem, moved) = match in_place_problem {
+        Some(problem) => (recorded_path, problem, false),
+        None => match find_moved_worktree(&settings.search_paths, admin_path) {
+            Some(new_path) => {
+                let problem = format!("moved from `{}`", recorded_path.display());
+                (new_path, problem, true)
+            }
+            // Whatever sits at the recorded path now is not this worktree, so it is left alone
+            None if recorded_path.is_dir() => {
+                let detail = match read_git_file(&recorded_git_file) {
+                    Some(target) => format!(
+                        "`{}` is now the worktree of `{}`",
+                        recorded_path.display(),
+                        target.display()
+                    ),
+                    None => format!("`{}` has no `.git` file", recorded_path.display()),
+                };
+                return Ok((Some(recorded_path), RepairStatus::Conflict, detail, false));
+            }
+            None => {
+                let worktree = repo.find_worktree(worktree_name)?;
+
+                if get_lock_reason(&worktree)?.is_some() {
+                    let detail = format!(
+                        "`{}` not found, kept because it is locked",
+                        recorded_path.display()
+                    );
+                    return Ok((Some(recorded_path), RepairStatus::Missing, detail, false));
+                }
+
+                if !settings.prune || settings.dry_run {
+                    let detail = format!(
+                        "`{}` not found, use --prune to remove its metadata",
+                        recorded_path.display()
+                    );
+                    return Ok((Some(recorded_path), RepairStatus::Missing, detail, false));
+                }
+
+                worktree.prune(Some(&mut WorktreePruneOptions::new()))?;
+
+                let detail = format!("`{}` not found, metadata removed", recorded_path.display());
+                return Ok((Some(recorded_path), RepairStatus::Pruned, detail, false));
+            }
+        },
+    };
+
+    if settings.dry_run || (settings.moved_only && !moved) {
+        return Ok((
+            Some(worktree_path),
+            RepairStatus::NeedsRepair,
+            problem,
+            moved,
+        ));
+    }
+
+    write_worktree_links(&worktree_path, admin_path)?;
+
+    Ok((Some(worktree_path), RepairStatus::Repaired, problem, moved))
+}
+
+/// Checks that each worktree and its metadata point at each other, relinking worktrees which
+/// were moved into one of the search paths and pruning missing ones when asked to. A directory
+/// at the recorded path which belongs to something else is reported as a conflict, never relinked
+pub(crate) fn repair_worktrees(
+    repo: &Repository,
+    mut settings: RepairSettings,
+) -> Result<Vec<RepairResult>> {
+    let common_dir = get_common_dir(repo);
+
+    if settings.search_paths.is_empty() {
+        settings.search_paths.extend(get_root_repo_path(repo).ok());
+
+        // Bare repositories keep worktrees in themselves, or next to them when added from
+        // another worktree
+        if let Some(parent) = common_dir.parent() {
+            if !settings.search_paths.iter().any(|path| path == parent) {
+                settings.search_paths.push(parent.to_path_buf());
+            }
+        }
+    }
+
+    let repair_results = repo
+        .worktrees()?
+        .iter()
+        .flatten()
+        .map(|worktree_name| {
+            let admin_path = common_dir.join("worktrees").join(worktree_name);
+
+            let (path, status, detail, moved) =
+                repair_worktree(repo, worktree_name, &admin_path, &settings).unwrap_or_else(|e| {
+                    error!("Failed to repair worktree `{}`: {:?}", worktree_name, e);
+
+                    (None, RepairStatus::Failed, format!("{:#}", e), false)
+                });
+
+            RepairResult {
+                worktree: worktree_name.to_string(),
+                path,
+                status,
+                detail,
+                moved,
+            }
+        })
+        .collect();
+
+    Ok(repair_results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::git::test_utils::{get_test_dir, init_repo};
+
+    use super::*;
+
+    fn repair(repo: &Repository, root: &Path) -> Vec<RepairResult> {
+        repair_worktrees(
+            repo,
+            RepairSettings {
+                search_paths: vec![root.to_path_buf()],
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    fn get_result<'a>(repair_results: &'a [RepairResult], worktree: &str) -> &'a RepairResult {
+        repair_results
+            .iter()
+            .find(|repair_result| repair_result.worktree == worktree)
+            .unwrap()
+    }
+
+    fn points_at(worktree_path: &Path, admin_path: &Path) -> bool {
+        read_git_file(&worktree_path.join(".git"))
+            .is_some_and(|target| is_same_path(&target, admin_path))
+    }
+
+    #[test]
+    fn moved_worktree_is_relinked() {
+        let root = get_test_dir("repair-moved");
+        let repo = init_repo(&root);
+        repo.worktree("a", &root.join("a"), None).unwrap();
+
+        fs::create_dir(root.join("moved")).unwrap();
+        fs::rename(root.join("a"), root.join("moved/a")).unwrap();
+
+        let repair_results = repair(&repo, &root);
+        let repair_result = get_result(&repair_results, "a");
+
+        assert_eq!(repair_result.status, RepairStatus::Repaired);
+        assert_eq!(repair_result.path, Some(root.join("moved/a")));
+        assert!(is_same_path(
+            repo.find_worktree("a").unwrap().path(),
+            &root.join("moved/a")
+        ));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn directory_without_git_file_is_not_relinked() {
+        let root = get_test_dir("repair-deleted");
+        let repo = init_repo(&root);
+        repo.worktree("a", &root.join("a"), None).unwrap();
+
+        fs::remove_file(root.join("a/.git")).unwrap();
+
+        let repair_results = repair(&repo, &root);
+
+        assert_eq!(
+            get_result(&repair_results, "a").status,
+            RepairStatus::Conflict
+        );
+        assert!(!root.join("a/.git").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn worktree_moved_into_the_path_of_another_keeps_its_git_file() {
+        let root = get_test_dir("repair-collision");
+        let repo = init_repo(&root);
+        repo.worktree("a", &root.join("a"), None).unwrap();
+        repo.worktree("b", &root.join("b"), None).unwrap();
+
+        fs::remove_dir_all(root.join("a")).unwrap();
+        fs::rename(root.join("b"), root.join("a")).unwrap();
+
+        let repair_results = repair(&repo, &root);
+        let admin_path = repo.path().join("worktrees");
+
+        assert_eq!(
+            get_result(&repair_results, "a").status,
+            RepairStatus::Conflict
+        );
+        assert_eq!(
+            get_result(&repair_results, "b").status,
+            RepairStatus::Repaired
+        );
+        assert!(points_at(&root.join("a"), &admin_path.join("b")));
+        assert!(is_same_path(
+            repo.find_worktree("b").unwrap().path(),
+            &root.join("a")
+        ));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn unreadable_git_file_is_relinked_in_place() {
+        let root = get_test_dir("repair-unreadable");
+        let repo = init_repo(&root);
+        repo.worktree("a", &root.join("a"), None).unwrap();
+
+        fs::write(root.join("a/.git"), "garbage").unwrap();
+
+        let repair_results = repair(&repo, &root);
+
+        assert_eq!(
+            get_result(&repair_results, "a").status,
+            RepairStatus::Repaired
+        );
+        assert!(points_at(
+            &root.join("a"),
+            &repo.path().join("worktrees").join("a")
+        ));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn moved_only_relinks_moved_worktrees_and_reports_the_rest() {
+        let root = get_test_dir("repair-moved-only");
+        let repo = init_repo(&root);
+        repo.worktree("a", &root.join("a"), None).unwrap();
+        repo.worktree("b", &root.join("b"), None).unwrap();
+
+        fs::create_dir(root.join("moved")).unwrap();
+        fs::rename(root.join("a"), root.join("moved/a")).unwrap();
+        fs::write(root.join("b/.git"), "garbage").unwrap();
+
+        let repair_results = repair_worktrees(
+            &repo,
+            RepairSettings {
+                search_paths: vec![root.clone()],
+                moved_only: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let moved = get_result(&repair_results, "a");
+        assert_eq!(moved.status, RepairStatus::Repaired);
+        assert!(moved.moved);
+
+        let in_place = get_result(&repair_results, "b");
+        assert_eq!(in_place.status, RepairStatus::NeedsRepair);
+        assert!(!in_place.moved);
+        assert_eq!(fs::read_to_string(root.join("b/.git")).unwrap(), "garbage");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
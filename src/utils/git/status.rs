@@ -0,0 +1,246 @@
+use anyhow::Result;
+use git2::{Oid, Repository, RepositoryState, Status, StatusOptions};
+use serde::{Serialize, Serializer};
+
+use super::{
+    sync::get_upstream_oid,
+    worktree::{format_head_label, get_worktrees, WorktreeInfo},
+};
+
+#[derive(Debug, Serialize)]
+pub(crate) struct AheadBehind {
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct WorktreeStatus {
+    pub name: String,
+    pub branch: Option<String>,
+    #[serde(serialize_with = "serialize_oid")]
+    pub head: Option<Oid>,
+    pub tag: Option<String>,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    pub stashes: usize,
+    pub upstream: Option<AheadBehind>,
+    pub base: Option<AheadBehind>,
+    pub operation: Option<&'static str>,
+    pub locked: Option<String>,
+    /// Why the status could not be read, the counts are unknown then
+    pub error: Option<String>,
+}
+
+fn serialize_oid<S>(oid: &Option<Oid>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    oid.map(|oid| oid.to_string()).serialize(serializer)
+}
+
+impl WorktreeStatus {
+    fn failed(worktree: WorktreeInfo, error: String) -> Self {
+        WorktreeStatus {
+            name: worktree.name,
+            branch: worktree.branch,
+            head: worktree.head,
+            tag: worktree.tag,
+            staged: 0,
+            unstaged: 0,
+            untracked: 0,
+            conflicted: 0,
+            stashes: 0,
+            upstream: None,
+            base: None,
+            operation: None,
+            locked: worktree.locked,
+            error: Some(error),
+        }
+    }
+
+    pub(crate) fn head_label(&self) -> String {
+        format_head_label(self.branch.as_deref(), self.tag.as_deref(), self.head)
+    }
+}
+
+pub(crate) fn format_count(count: usize) -> String {
+    if count == 0 {
+        String::from("-")
+    } else {
+        count.to_string()
+    }
+}
+
+pub(crate) fn format_ahead_behind(ahead_behind: &Option<AheadBehind>) -> String {
+    match ahead_behind {
+        Some(AheadBehind {
+            ahead: 0,
+            behind: 0,
+        }) => String::from("="),
+        Some(AheadBehind { ahead, behind }) => format!("↑{} ↓{}", ahead, behind),
+        None => String::from("-"),
+    }
+}
+
+fn get_operation(state: RepositoryState) -> Option<&'static str> {
+    match state {
+        RepositoryState::Clean => None,
+        RepositoryState::Merge => Some("merge"),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some("revert"),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some("cherry-pick"),
+        RepositoryState::Bisect => Some("bisect"),
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailboxOrRebase => Some("rebase"),
+        RepositoryState::ApplyMailbox => Some("am"),
+    }
+}
+
+/// `origin/HEAD` when the remote advertised it, otherwise the first of `main` and `master`
+pub(crate) fn get_default_base_branch(repo: &Repository) -> Option<String> {
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = reference.symbolic_target() {
+            return target.strip_prefix("refs/remotes/").map(String::from);
+        }
+    }
+
+    ["main", "master"]
+        .into_iter()
+        .find(|name| repo.refname_to_id(&format!("refs/heads/{name}")).is_ok())
+        .map(String::from)
+}
+
+fn get_ahead_behind(repo: &Repository, local: Oid, upstream: Option<Oid>) -> Option<AheadBehind> {
+    let (ahead, behind) = repo.graph_ahead_behind(local, upstream?).ok()?;
+
+    Some(AheadBehind { ahead, behind })
+}
+
+/// Stashes are shared by all worktrees, so only the ones made on the branch are counted
+fn count_stashes(repo: &mut Repository, branch: Option<&str>) -> usize {
+    let Some(branch) = branch else {
+        return 0;
+    };
+
+    let prefixes = [format!("WIP on {branch}:"), format!("On {branch}:")];
+    let mut stashes = 0;
+
+    let _ = repo.stash_foreach(|_, message, _| {
+        if prefixes.iter().any(|prefix| message.starts_with(prefix)) {
+            stashes += 1;
+        }
+
+        true
+    });
+
+    stashes
+}
+
+fn get_worktree_status(
+    repo: &Repository,
+    worktree: WorktreeInfo,
+    base: Option<&str>,
+) -> Result<WorktreeStatus> {
+    let WorktreeInfo {
+        name,
+        branch,
+        head,
+        tag,
+        locked,
+        ..
+    } = worktree;
+
+    let worktree = repo.find_worktree(&name)?;
+    let mut worktree_repo = Repository::open_from_worktree(&worktree)?;
+
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(false);
+
+    let (mut staged, mut unstaged, mut untracked, mut conflicted) = (0, 0, 0, 0);
+
+    for entry in worktree_repo.statuses(Some(&mut status_options))?.iter() {
+        let status = entry.status();
+
+        if status.is_conflicted() {
+            conflicted += 1;
+            continue;
+        }
+
+        if status.intersects(
+            Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            staged += 1;
+        }
+
+        if status.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+        ) {
+            unstaged += 1;
+        }
+
+        if status.is_wt_new() {
+            untracked += 1;
+        }
+    }
+
+    let (upstream, base) = match (&branch, head) {
+        (Some(branch), Some(head)) => {
+            let upstream = get_ahead_behind(
+                &worktree_repo,
+                head,
+                get_upstream_oid(&worktree_repo, branch),
+            );
+
+            // The base branch is not compared with itself
+            let base = base
+                .filter(|base| *base != branch && *base != format!("origin/{branch}"))
+                .and_then(|base| {
+                    let base = worktree_repo.revparse_single(base).ok()?.id();
+                    get_ahead_behind(&worktree_repo, head, Some(base))
+                });
+
+            (upstream, base)
+        }
+        _ => (None, None),
+    };
+
+    Ok(WorktreeStatus {
+        name,
+        stashes: count_stashes(&mut worktree_repo, branch.as_deref()),
+        branch,
+        head,
+        tag,
+        staged,
+        unstaged,
+        untracked,
+        conflicted,
+        upstream,
+        base,
+        operation: get_operation(worktree_repo.state()),
+        locked,
+        error: None,
+    })
+}
+
+/// Worktrees whose status cannot be read are kept, with the error instead of the counts
+pub(crate) fn get_worktree_statuses(repo: &Repository, base: Option<&str>) -> Vec<WorktreeStatus> {
+    get_worktrees(repo)
+        .into_iter()
+        .map(|worktree| {
+            get_worktree_status(repo, worktree.clone(), base).unwrap_or_else(|e| {
+                warn!("Failed to get status of `{}`: {:#}", worktree.name, e);
+
+                WorktreeStatus::failed(worktree, format!("{:#}", e))
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+
+use anyhow::Result;
+use git2::{build::CheckoutBuilder, BranchType, Oid, Repository, RepositoryState};
+
+use super::{
+    fetch::{fetch_branches, FetchSettings},
+    is_branch_clear,
+    rebase::{rebase_onto, UpdateOutcome},
+    worktree::get_worktree_names,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SyncStatus {
+    Updated,
+    UpToDate,
+    Skipped,
+    Diverged,
+    Conflicted,
+    Failed,
+}
+
+impl fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncStatus::Updated => write!(f, "updated"),
+            SyncStatus::UpToDate => write!(f, "up to date"),
+            SyncStatus::Skipped => write!(f, "skipped"),
+            SyncStatus::Diverged => write!(f, "diverged"),
+            SyncStatus::Conflicted => write!(f, "conflicted"),
+            SyncStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SyncResult {
+    pub worktree: String,
+    pub branch: Option<String>,
+    pub status: SyncStatus,
+    pub detail: String,
+}
+
+/// Name of the branch on origin the local branch tracks, defaults to the same name
+fn get_upstream_branch_name(repo: &Repository, branch_name: &str) -> String {
+    repo.config()
+        .and_then(|config| config.get_string(&format!("branch.{branch_name}.merge")))
+        .ok()
+        .and_then(|merge| merge.strip_prefix("refs/heads/").map(String::from))
+        .unwrap_or_else(|| branch_name.to_string())
+}
+
+/// Remote other than origin the branch tracks, which isn't fetched and so can't be synced with.
+/// `.` is the local repository and needs no fetch
+fn get_other_upstream_remote_name(repo: &Repository, branch_name: &str) -> Option<String> {
+    let remote_name = repo
+        .config()
+        .and_then(|config| config.get_string(&format!("branch.{branch_name}.remote")))
+        .ok()?;
+
+    (remote_name != "origin" && remote_name != ".").then_some(remote_name)
+}
+
+pub(crate) fn get_upstream_oid(repo: &Repository, branch_name: &str) -> Option<Oid> {
+    let branch = repo.find_branch(branch_name, BranchType::Local).ok()?;
+
+    match branch.upstream() {
+        Ok(upstream) => upstream.get().target(),
+        // Bare clones don't configure upstreams, so fall back to the branch of the same name
+        Err(_) => repo
+            .refname_to_id(&format!("refs/remotes/origin/{branch_name}"))
+            .ok(),
+    }
+}
+
+pub(crate) fn get_head_branch_name(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+
+    if !head.is_branch() {
+        return None;
+    }
+
+    head.shorthand().map(String::from)
+}
+
+pub(crate) fn fast_forward(repo: &Repository, target: Oid) -> Result<(), git2::Error> {
+    let commit = repo.find_commit(target)?;
+
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.head()?.set_target(target, "sync: fast-forward")?;
+
+    Ok(())
+}
+
+/// Why a worktree can't be updated safely, if at all
+pub(crate) fn get_skip_reason(repo: &Repository) -> Option<String> {
+    if repo.state() != RepositoryState::Clean {
+        return Some(format!("{:?} in progress", repo.state()));
+    }
+
+    if !is_branch_clear(repo) {
+        return Some(String::from("uncommitted changes"));
+    }
+
+    None
+}
+
+fn sync_worktree(repo: &Repository, rebase: bool) -> Result<(Option<String>, SyncStatus, String)> {
+    let Some(branch_name) = get_head_branch_name(repo) else {
+        return Ok((None, SyncStatus::Skipped, String::from("detached HEAD")));
+    };
+
+    let branch = Some(branch_name.clone());
+
+    if let Some(reason) = get_skip_reason(repo) {
+        return Ok((branch, SyncStatus::Skipped, reason));
+    }
+
+    if let Some(remote_name) = get_other_upstream_remote_name(repo, &branch_name) {
+        let detail = format!("tracks `{remote_name}`, only origin is fetched");
+        return Ok((branch, SyncStatus::Skipped, detail));
+    }
+
+    let Some(upstream) = get_upstream_oid(repo, &branch_name) else {
+        return Ok((branch, SyncStatus::Skipped, String::from("no upstream")));
+    };
+
+    let local = repo.refname_to_id(&format!("refs/heads/{branch_name}"))?;
+    let (ahead, behind) = repo.graph_ahead_behind(local, upstream)?;
+
+    if behind == 0 {
+        let detail = if ahead > 0 {
+            format!("ahead by {ahead}")
+        } else {
+            String::new()
+        };
+
+        return Ok((branch, SyncStatus::UpToDate, detail));
+    }
+
+    if ahead == 0 {
+        fast_forward(repo, upstream)?;
+
+        let detail = format!("fast-forwarded {behind} commit(s)");
+        return Ok((branch, SyncStatus::Updated, detail));
+    }
+
+    if !rebase {
+        let detail = format!("ahead by {ahead}, behind by {behind}");
+        return Ok((branch, SyncStatus::Diverged, detail));
+    }
+
+    let upstream = repo.find_annotated_commit(upstream)?;
+
+    match rebase_onto(repo, &upstream)? {
+        UpdateOutcome::Updated => {
+            let detail = format!("rebased {ahead} commit(s) onto {behind} new commit(s)");
+            Ok((branch, SyncStatus::Updated, detail))
+        }
+        UpdateOutcome::Conflicted(paths) => {
+            let detail = format!("rebase aborted, conflicts in {}", paths.join(", "));
+            Ok((branch, SyncStatus::Conflicted, detail))
+        }
+    }
+}
+
+/// Fetches the upstreams of all worktrees at once, then brings each clean worktree up to date
+pub(crate) fn sync_worktrees(
+    repo: &Repository,
+    rebase: bool,
+    fetch_settings: &FetchSettings,
+) -> Result<Vec<SyncResult>> {
+    let worktrees = get_worktree_names(repo)
+        .into_iter()
+        .map(|name| {
+            let worktree_repo = repo
+                .find_worktree(&name)
+                .and_then(|worktree| Repository::open_from_worktree(&worktree));
+
+            (name, worktree_repo)
+        })
+        .collect::<Vec<_>>();
+
+    let upstream_branch_names = worktrees
+        .iter()
+        .filter_map(|(_, worktree_repo)| {
+            let worktree_repo = worktree_repo.as_ref().ok()?;
+            let branch_name = get_head_branch_name(worktree_repo)?;
+
+            if get_other_upstream_remote_name(worktree_repo, &branch_name).is_some() {
+                return None;
+            }
+
+            Some(get_upstream_branch_name(worktree_repo, &branch_name))
+        })
+        .collect::<Vec<String>>();
+
+    fetch_branches(repo, &upstream_branch_names, &[], fetch_settings)?;
+
+    let sync_results = worktrees
+        .into_iter()
+        .map(|(worktree, worktree_repo)| {
+            let result = worktree_repo
+                .map_err(anyhow::Error::from)
+                .and_then(|worktree_repo| sync_worktree(&worktree_repo, rebase));
+
+            let (branch, status, detail) = result.unwrap_or_else(|e| {
+                error!("Failed to sync worktree `{}`: {:?}", worktree, e);
+
+                (None, SyncStatus::Failed, format!("{:#}", e))
+            });
+
+            SyncResult {
+                worktree,
+                branch,
+                status,
+                detail,
+            }
+        })
+        .collect();
+
+    Ok(sync_results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use git2::WorktreeAddOptions;
+
+    use crate::utils::git::test_utils::{commit_file, get_test_dir, init_repo};
+
+    use super::*;
+
+    const NO_FETCH: FetchSettings = FetchSettings {
+        quiet: true,
+        skip: true,
+        full: false,
+        depth: None,
+    };
+
+    #[test]
+    fn branch_tracking_another_remote_is_skipped() {
+        let root = get_test_dir("sync-other-remote");
+        let repo = init_repo(&root);
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        let branch = repo.branch("feat", &base, false).unwrap();
+
+        // The upstream branch is ahead, but only ever fetched by hand from `fork`
+        let ahead = commit_file(&repo, "file", "ahead\n", "Ahead");
+        repo.remote("fork", "https://forge.test/fork/repo.git")
+            .unwrap();
+        repo.reference("refs/remotes/fork/feat", ahead, true, "")
+            .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("branch.feat.remote", "fork").unwrap();
+        config
+            .set_str("branch.feat.merge", "refs/heads/feat")
+            .unwrap();
+
+        repo.worktree(
+            "feat",
+            &root.join("feat"),
+            Some(WorktreeAddOptions::new().reference(Some(branch.get()))),
+        )
+        .unwrap();
+
+        let sync_results = sync_worktrees(&repo, false, &NO_FETCH).unwrap();
+
+        assert_eq!(sync_results.len(), 1);
+        assert_eq!(sync_results[0].status, SyncStatus::Skipped);
+        assert_eq!(
+            sync_results[0].detail,
+            "tracks `fork`, only origin is fetched"
+        );
+        assert_eq!(repo.refname_to_id("refs/heads/feat").unwrap(), base.id());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,54 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use git2::{Oid, Repository, Signature};
+
+/// Empty directory for a test, named after it so tests running in parallel don't collide
+pub(crate) fn get_test_dir(test_name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("worktree-cli-{}-{}", test_name, process::id()));
+
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+/// Repository at `<root>/repo` with `file` committed on `main`
+pub(crate) fn init_repo(root: &Path) -> Repository {
+    let repo = Repository::init(root.join("repo")).unwrap();
+
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+
+    repo.set_head("refs/heads/main").unwrap();
+    commit_file(&repo, "file", "initial\n", "Initial commit");
+
+    repo
+}
+
+/// Writes `contents` to `path` in the working tree and commits it on HEAD
+pub(crate) fn commit_file(repo: &Repository, path: &str, contents: &str, message: &str) -> Oid {
+    fs::write(repo.workdir().unwrap().join(path), contents).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("test", "test@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
@@ -1,87 +1,160 @@
-use std::ffi::OsStr;
-
-use anyhow::{bail, Result};
-use git2::{Error, Repository, Worktree, WorktreeAddOptions};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::utils::git::common::get_root_repo_path;
+use anyhow::{bail, Context, Result};
+use git2::{
+    BranchType, Commit, Error, Oid, Repository, StatusOptions, Worktree, WorktreeAddOptions,
+    WorktreeLockStatus, WorktreePruneOptions,
+};
 
-use super::{
-    branch::{get_local_branch_reference, get_worktree_branch, BranchInfo},
-    commit::get_worktree_commit_time,
+use crate::utils::git::{
+    alias::{get_aliases_of, remove_aliases_of, rename_aliases_of},
+    common::get_root_repo_path,
 };
 
-pub(crate) fn get_worktree_names(repo: &Repository) -> Vec<String> {
-    let worktree_names = repo.worktrees().unwrap();
+use super::branch::{get_local_branch_reference, BranchInfo};
+
+/// A worktree as recorded in the repository metadata, independent of where it lives on disk
+#[derive(Debug, Clone)]
+pub(crate) struct WorktreeInfo {
+    pub name: String,
+    pub path: PathBuf,
+    /// Branch checked out in the worktree, `None` when its HEAD is detached
+    pub branch: Option<String>,
+    pub head: Option<Oid>,
+    /// Tag pointing at HEAD, only looked up for detached worktrees
+    pub tag: Option<String>,
+    pub commit_time: Option<i64>,
+    pub locked: Option<String>,
+    pub aliases: Vec<String>,
+}
+
+impl WorktreeInfo {
+    pub(crate) fn head_label(&self) -> String {
+        format_head_label(self.branch.as_deref(), self.tag.as_deref(), self.head)
+    }
+}
+
+/// Branch name, or the tag or short commit id of a detached HEAD in parentheses
+pub(crate) fn format_head_label(
+    branch: Option<&str>,
+    tag: Option<&str>,
+    head: Option<Oid>,
+) -> String {
+    match (branch, tag, head) {
+        (Some(branch), _, _) => branch.to_string(),
+        (None, Some(tag), _) => format!("({})", tag),
+        (None, None, Some(head)) => format!("({:.7})", head.to_string()),
+        (None, None, None) => String::from("(detached)"),
+    }
+}
+
+fn get_tag_at(repo: &Repository, oid: Oid) -> Option<String> {
+    let tag_names = repo.tag_names(None).ok()?;
+
+    let tag_name = tag_names.iter().flatten().find(|tag_name| {
+        repo.revparse_single(&format!("refs/tags/{}", tag_name))
+            .and_then(|object| object.peel_to_commit())
+            .is_ok_and(|commit| commit.id() == oid)
+    });
 
-    let mut worktree_paths = worktree_names
+    tag_name.map(str::to_string)
+}
+
+pub(crate) fn get_worktree_info(
+    repo: &Repository,
+    worktree_name: &str,
+) -> Result<WorktreeInfo, Error> {
+    let worktree = repo.find_worktree(worktree_name)?;
+    let worktree_repo = Repository::open_from_worktree(&worktree)?;
+
+    // The per-worktree HEAD, unborn branches have a name but no commit yet
+    let head = worktree_repo.find_reference("HEAD")?;
+    let (branch, head) = match head.symbolic_target() {
+        Some(target) => (
+            target.strip_prefix("refs/heads/").map(str::to_string),
+            worktree_repo.refname_to_id(target).ok(),
+        ),
+        None => (None, head.target()),
+    };
+
+    let tag = match (&branch, head) {
+        (None, Some(head)) => get_tag_at(repo, head),
+        _ => None,
+    };
+
+    let commit_time = head
+        .and_then(|head| repo.find_commit(head).ok())
+        .map(|commit| commit.time().seconds());
+
+    Ok(WorktreeInfo {
+        name: worktree_name.to_string(),
+        path: worktree.path().to_path_buf(),
+        branch,
+        head,
+        tag,
+        commit_time,
+        locked: get_lock_reason(&worktree)?,
+        aliases: get_aliases_of(repo, worktree_name),
+    })
+}
+
+/// All worktrees with readable metadata, most recently committed to first
+pub(crate) fn get_worktrees(repo: &Repository) -> Vec<WorktreeInfo> {
+    let Ok(worktree_names) = repo.worktrees() else {
+        return Vec::new();
+    };
+
+    let mut worktrees = worktree_names
         .iter()
+        .flatten()
         .filter_map(|worktree_name| {
-            if let Some(worktree_name) = worktree_name {
-                let worktree = repo.find_worktree(worktree_name).ok()?;
-                let commit_time = get_worktree_commit_time(repo, worktree_name)?;
-
-                Some((worktree.name().unwrap().to_string(), commit_time))
-            } else {
-                None
-            }
+            get_worktree_info(repo, worktree_name)
+                .map_err(|e| warn!("Failed to read worktree `{}`: {}", worktree_name, e))
+                .ok()
         })
-        .collect::<Vec<(String, i64)>>();
+        .collect::<Vec<WorktreeInfo>>();
+
+    worktrees.sort_by(|a, b| b.commit_time.cmp(&a.commit_time));
 
-    worktree_paths.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by commit time in descending order
+    worktrees
+}
 
-    worktree_paths.into_iter().map(|(name, _)| name).collect()
+pub(crate) fn get_worktree_names(repo: &Repository) -> Vec<String> {
+    get_worktrees(repo)
+        .into_iter()
+        .map(|worktree| worktree.name)
+        .collect()
 }
 
 pub(crate) fn get_worktree_path_by_name(
     repo: &Repository,
     target_worktree_name: &str,
 ) -> Result<String> {
-    let worktree_names = repo.worktrees().unwrap();
-
-    for worktree_name in worktree_names.iter().flatten() {
-        let worktree = repo.find_worktree(worktree_name).unwrap();
-        let worktree_name = worktree.name().expect("Failed to get worktree name");
+    let worktree = get_worktree_by_name(repo, &normalize_workspace_name(target_worktree_name))?;
 
-        if worktree_name == normalize_workspace_name(target_worktree_name) {
-            return Ok(worktree.path().to_string_lossy().to_string());
-        }
-    }
-
-    bail!("Worktree not found");
+    Ok(worktree.path().to_string_lossy().to_string())
 }
 
 pub(crate) fn get_worktree_by_branch_name(
     repo: &Repository,
     branch_name: &str,
 ) -> Result<String, Error> {
-    let worktree_names = repo.worktrees()?;
-
-    for worktree_name in worktree_names.iter().flatten() {
-        if let Ok(worktree_branch) = get_worktree_branch(repo, worktree_name) {
-            if worktree_branch == branch_name {
-                return Ok(worktree_name.to_string());
-            }
-        }
-    }
-
-    Err(Error::from_str(
-        "No worktree found for the given branch name",
-    ))
+    get_worktrees(repo)
+        .into_iter()
+        .find(|worktree| worktree.branch.as_deref() == Some(branch_name))
+        .map(|worktree| worktree.name)
+        .ok_or_else(|| Error::from_str("No worktree found for the given branch name"))
 }
 
 pub(crate) fn worktree_exists_by_branch_name(
     repo: &Repository,
     branch_name: &str,
 ) -> Result<bool, Error> {
-    let worktree_names = repo.worktrees()?;
-
-    Ok(worktree_names.iter().flatten().any(|name| {
-        if let Ok(worktree_branch) = get_worktree_branch(repo, name) {
-            worktree_branch == branch_name
-        } else {
-            false
-        }
-    }))
+    Ok(get_worktree_by_branch_name(repo, branch_name).is_ok())
 }
 
 pub(crate) fn worktree_exists_by_name<S>(
@@ -166,6 +239,512 @@ where
     ))
 }
 
-fn normalize_workspace_name(workspace_name: &str) -> String {
+/// Adds a worktree with a detached HEAD at `commit`. libgit2 can only check out a branch in a
+/// new worktree, so a temporary branch is used and deleted once HEAD is detached
+pub(crate) fn add_detached_worktree(
+    repo: &Repository,
+    worktree_name: &str,
+    commit: &Commit,
+) -> Result<(Worktree, AddKind)> {
+    let worktree_name = normalize_workspace_name(worktree_name);
+
+    if worktree_exists_by_name(repo, &worktree_name)? {
+        warn!("Worktree with name `{}` already exists", worktree_name);
+
+        return Ok((
+            get_worktree_by_name(repo, &worktree_name)?,
+            AddKind::Existed,
+        ));
+    }
+
+    let worktree_path = get_root_repo_path(repo)?.join(&worktree_name);
+
+    let temporary_branch_name = format!("worktree-cli/detach/{}", worktree_name);
+    let mut temporary_branch = repo.branch(&temporary_branch_name, commit, false)?;
+
+    let mut add_options = WorktreeAddOptions::new();
+    add_options.reference(Some(temporary_branch.get()));
+
+    let worktree = repo.worktree(&worktree_name, &worktree_path, Some(&add_options));
+
+    let detached = worktree.and_then(|worktree| {
+        Repository::open_from_worktree(&worktree)?.set_head_detached(commit.id())?;
+
+        Ok(worktree)
+    });
+
+    // A half-added worktree still has the temporary branch checked out, so it goes first
+    if detached.is_err() {
+        if let Ok(worktree) = repo.find_worktree(&worktree_name) {
+            let mut prune_options = WorktreePruneOptions::new();
+            prune_options.valid(true).locked(true).working_tree(true);
+
+            if let Err(e) = worktree.prune(Some(&mut prune_options)) {
+                warn!("Failed to remove worktree `{}`: {}", worktree_name, e);
+            }
+        }
+    }
+
+    let deleted = temporary_branch.delete();
+
+    // The error of adding the worktree matters more than the leftover branch
+    let worktree = detached?;
+    deleted?;
+
+    Ok((worktree, AddKind::Added))
+}
+
+/// Deletes the worktree directory and its metadata, locked worktrees and worktrees with
+/// uncommitted or untracked files are only removed with `force`
+pub(crate) fn remove_worktree(repo: &Repository, worktree_name: &str, force: bool) -> Result<()> {
+    let worktree = get_worktree_by_name(repo, &worktree_name)?;
+
+    if !force {
+        if get_lock_reason(&worktree)?.is_some() {
+            bail!(
+                "Worktree `{}` is locked, use --force to remove it",
+                worktree_name
+            );
+        }
+
+        if let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) {
+            let mut status_options = StatusOptions::new();
+            status_options.include_untracked(true);
+
+            if !worktree_repo
+                .statuses(Some(&mut status_options))?
+                .is_empty()
+            {
+                bail!("Worktree `{}` has uncommitted changes", worktree_name);
+            }
+        }
+    }
+
+    let mut prune_options = WorktreePruneOptions::new();
+    prune_options.valid(true).locked(force).working_tree(true);
+
+    worktree.prune(Some(&mut prune_options))?;
+
+    remove_aliases_of(repo, worktree_name)?;
+
+    Ok(())
+}
+
+pub(crate) fn get_lock_reason(worktree: &Worktree) -> Result<Option<String>, Error> {
+    Ok(match worktree.is_locked()? {
+        WorktreeLockStatus::Unlocked => None,
+        // `git worktree lock` stores the reason with a trailing newline
+        WorktreeLockStatus::Locked(reason) => Some(reason.unwrap_or_default().trim().to_string()),
+    })
+}
+
+pub(crate) fn lock_worktree(
+    repo: &Repository,
+    worktree_name: &str,
+    reason: Option<&str>,
+) -> Result<()> {
+    let worktree = get_worktree_by_name(repo, &worktree_name)?;
+
+    if get_lock_reason(&worktree)?.is_some() {
+        bail!("Worktree `{}` is already locked", worktree_name);
+    }
+
+    Ok(worktree.lock(reason)?)
+}
+
+pub(crate) fn unlock_worktree(repo: &Repository, worktree_name: &str) -> Result<()> {
+    let worktree = get_worktree_by_name(repo, &worktree_name)?;
+
+    if get_lock_reason(&worktree)?.is_none() {
+        bail!("Worktree `{}` is not locked", worktree_name);
+    }
+
+    Ok(worktree.unlock()?)
+}
+
+/// Removes the metadata of worktrees whose directory is gone, locked ones are kept unless
+/// `force`. Returns the pruned and the kept worktrees
+pub(crate) fn prune_worktrees(
+    repo: &Repository,
+    force: bool,
+) -> Result<(Vec<String>, Vec<String>)> {
+    let mut pruned = Vec::new();
+    let mut kept = Vec::new();
+
+    for worktree_name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(worktree_name)?;
+
+        if worktree.path().exists() {
+            continue;
+        }
+
+        if !force && get_lock_reason(&worktree)?.is_some() {
+            kept.push(worktree_name.to_string());
+            continue;
+        }
+
+        let mut prune_options = WorktreePruneOptions::new();
+        prune_options.locked(force);
+
+        worktree.prune(Some(&mut prune_options))?;
+        remove_aliases_of(repo, worktree_name)?;
+        pruned.push(worktree_name.to_string());
+    }
+
+    Ok((pruned, kept))
+}
+
+/// Points the `.git` file of the worktree and the `gitdir` file of its metadata at each other
+pub(crate) fn write_worktree_links(worktree_path: &Path, admin_path: &Path) -> Result<()> {
+    fs::write(
+        worktree_path.join(".git"),
+        format!("gitdir: {}\n", admin_path.display()),
+    )?;
+    fs::write(
+        admin_path.join("gitdir"),
+        format!("{}\n", worktree_path.join(".git").display()),
+    )?;
+
+    Ok(())
+}
+
+/// Renames the branch checked out in the worktree to `new_name`, then moves the worktree
+/// directory and its metadata to the normalized new name, returns the new worktree path.
+/// Locked worktrees are only moved with `force`, the lock moves with the metadata
+pub(crate) fn move_worktree(
+    repo: &Repository,
+    old_name: &str,
+    new_name: &str,
+    force: bool,
+) -> Result<PathBuf> {
+    let new_worktree_name = normalize_workspace_name(new_name);
+
+    let worktree = get_worktree_by_name(repo, &normalize_workspace_name(old_name))?;
+    let old_worktree_name = worktree.name().unwrap_or_default().to_string();
+
+    if !force && get_lock_reason(&worktree)?.is_some() {
+        bail!(
+            "Worktree `{}` is locked, use --force to move it",
+            old_worktree_name
+        );
+    }
+
+    if old_worktree_name == new_worktree_name {
+        bail!("Worktree is already named `{}`", new_worktree_name);
+    }
+
+    if worktree_exists_by_name(repo, &new_worktree_name)? {
+        bail!("Worktree `{}` already exists", new_worktree_name);
+    }
+
+    let worktree_repo = Repository::open_from_worktree(&worktree)
+        .with_context(|| format!("Failed to open worktree `{}`", old_worktree_name))?;
+
+    let old_worktree_path = worktree.path().to_path_buf();
+    let new_worktree_path = old_worktree_path
+        .parent()
+        .context("Failed to get worktree parent directory")?
+        .join(&new_worktree_name);
+
+    if new_worktree_path.exists() {
+        bail!("`{}` already exists", new_worktree_path.display());
+    }
+
+    let old_admin_path = worktree_repo.path().to_path_buf();
+    let new_admin_path = old_admin_path
+        .parent()
+        .context("Failed to get worktree metadata directory")?
+        .join(&new_worktree_name);
+
+    let head = worktree_repo.head()?;
+    let branch_name = head
+        .is_branch()
+        .then(|| head.shorthand())
+        .flatten()
+        .filter(|branch_name| *branch_name != new_name)
+        .map(str::to_string);
+
+    if branch_name.is_some() && repo.find_branch(new_name, BranchType::Local).is_ok() {
+        bail!("Branch `{}` already exists", new_name);
+    }
+
+    move_worktree_dirs(
+        &old_worktree_path,
+        &new_worktree_path,
+        &old_admin_path,
+        &new_admin_path,
+    )?;
+
+    // Renamed last, so a failure only has to undo the moves above
+    if let Some(branch_name) = branch_name {
+        // Also updates the HEAD of the worktree and moves the `branch.<name>` config
+        let renamed = repo
+            .find_branch(&branch_name, BranchType::Local)
+            .and_then(|mut branch| branch.rename(new_name, false).map(|_| ()));
+
+        if let Err(e) = renamed {
+            undo_worktree_move(
+                &old_worktree_path,
+                &new_worktree_path,
+                &old_admin_path,
+                &new_admin_path,
+            );
+
+            return Err(e.into());
+        }
+    }
+
+    rename_aliases_of(repo, &old_worktree_name, &new_worktree_name)?;
+
+    Ok(new_worktree_path)
+}
+
+/// Moves the worktree and its metadata, undoing what was already moved when a step fails
+fn move_worktree_dirs(
+    old_worktree_path: &Path,
+    new_worktree_path: &Path,
+    old_admin_path: &Path,
+    new_admin_path: &Path,
+) -> Result<()> {
+    fs::rename(old_worktree_path, new_worktree_path).with_context(|| {
+        format!(
+            "Failed to move `{}` to `{}`",
+            old_worktree_path.display(),
+            new_worktree_path.display()
+        )
+    })?;
+
+    if let Err(e) = fs::rename(old_admin_path, new_admin_path) {
+        let _ = fs::rename(new_worktree_path, old_worktree_path);
+
+        return Err(e).with_context(|| {
+            format!(
+                "Failed to move `{}` to `{}`",
+                old_admin_path.display(),
+                new_admin_path.display()
+            )
+        });
+    }
+
+    if let Err(e) = write_worktree_links(new_worktree_path, new_admin_path) {
+        undo_worktree_move(
+            old_worktree_path,
+            new_worktree_path,
+            old_admin_path,
+            new_admin_path,
+        );
+
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Best effort, the error which made the move fail is the one reported
+fn undo_worktree_move(
+    old_worktree_path: &Path,
+    new_worktree_path: &Path,
+    old_admin_path: &Path,
+    new_admin_path: &Path,
+) {
+    let _ = fs::rename(new_admin_path, old_admin_path);
+    let _ = fs::rename(new_worktree_path, old_worktree_path);
+    let _ = write_worktree_links(old_worktree_path, old_admin_path);
+}
+
+pub(crate) fn normalize_workspace_name(workspace_name: &str) -> String {
     workspace_name.replace("/", "_")
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::git::{
+        alias::add_alias,
+        test_utils::{commit_file, get_test_dir, init_repo},
+    };
+
+    use super::*;
+
+    /// Worktree `<root>/<name>` on a new branch `name` at HEAD
+    fn add_branch_worktree(repo: &Repository, root: &Path, name: &str) -> Worktree {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let branch = repo.branch(name, &head, false).unwrap();
+
+        repo.worktree(
+            name,
+            &root.join(name),
+            Some(WorktreeAddOptions::new().reference(Some(branch.get()))),
+        )
+        .unwrap()
+    }
+
+    fn get_head_name(worktree: &Worktree) -> String {
+        let worktree_repo = Repository::open_from_worktree(worktree).unwrap();
+        let head = worktree_repo.head().unwrap();
+
+        head.name().unwrap().to_string()
+    }
+
+    #[test]
+    fn detached_worktree_leaves_no_temporary_branch() {
+        let root = get_test_dir("worktree-detached");
+        let repo = init_repo(&root);
+        let first = repo.head().unwrap().target().unwrap();
+        commit_file(&repo, "file", "second\n", "Second commit");
+
+        let (worktree, add_kind) =
+            add_detached_worktree(&repo, "v1", &repo.find_commit(first).unwrap()).unwrap();
+
+        let worktree_repo = Repository::open_from_worktree(&worktree).unwrap();
+        assert_eq!(add_kind, AddKind::Added);
+        assert!(worktree_repo.head_detached().unwrap());
+        assert_eq!(worktree_repo.head().unwrap().target(), Some(first));
+        assert!(repo
+            .find_branch("worktree-cli/detach/v1", BranchType::Local)
+            .is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn moved_worktree_takes_its_metadata_branch_and_aliases_along() {
+        let root = get_test_dir("worktree-move");
+        let repo = init_repo(&root);
+        add_branch_worktree(&repo, &root, "feat");
+        add_alias(&repo, "f", "feat").unwrap();
+
+        let new_path = move_worktree(&repo, "feat", "feature/new", false).unwrap();
+
+        assert_eq!(new_path, root.join("feature_new"));
+        assert!(new_path.join("file").exists());
+        assert!(!root.join("feat").exists());
+
+        let worktree = repo.find_worktree("feature_new").unwrap();
+        assert!(worktree.validate().is_ok());
+        assert_eq!(worktree.path(), new_path);
+        assert!(repo.find_worktree("feat").is_err());
+
+        assert_eq!(get_head_name(&worktree), "refs/heads/feature/new");
+        assert!(repo.find_branch("feat", BranchType::Local).is_err());
+
+        assert_eq!(get_aliases_of(&repo, "feature_new"), vec!["f"]);
+        assert!(get_aliases_of(&repo, "feat").is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn locked_worktree_is_only_moved_with_force_and_stays_locked() {
+        let root = get_test_dir("worktree-move-locked");
+        let repo = init_repo(&root);
+        add_branch_worktree(&repo, &root, "feat");
+        lock_worktree(&repo, "feat", Some("on a usb stick")).unwrap();
+
+        assert!(move_worktree(&repo, "feat", "renamed", false).is_err());
+        assert!(root.join("feat").exists());
+
+        let new_path = move_worktree(&repo, "feat", "renamed", true).unwrap();
+
+        let worktree = repo.find_worktree("renamed").unwrap();
+        assert_eq!(worktree.path(), new_path);
+        assert_eq!(
+            get_lock_reason(&worktree).unwrap().as_deref(),
+            Some("on a usb stick")
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn failed_branch_rename_moves_the_worktree_back() {
+        let root = get_test_dir("worktree-move-rollback");
+        let repo = init_repo(&root);
+        add_branch_worktree(&repo, &root, "feat");
+
+        // Not a valid branch name, so only the rename at the end fails
+        assert!(move_worktree(&repo, "feat", "bad..name", false).is_err());
+
+        assert!(!root.join("bad..name").exists());
+        assert!(repo.find_worktree("bad..name").is_err());
+
+        let worktree = repo.find_worktree("feat").unwrap();
+        assert!(worktree.validate().is_ok());
+        assert_eq!(worktree.path(), root.join("feat"));
+        assert_eq!(get_head_name(&worktree), "refs/heads/feat");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn dirty_worktree_is_only_removed_with_force() {
+        let root = get_test_dir("worktree-remove-dirty");
+        let repo = init_repo(&root);
+        add_branch_worktree(&repo, &root, "feat");
+        add_alias(&repo, "f", "feat").unwrap();
+        fs::write(root.join("feat/untracked"), "new\n").unwrap();
+
+        let error = remove_worktree(&repo, "feat", false).unwrap_err();
+
+        assert_eq!(error.to_string(), "Worktree `feat` has uncommitted changes");
+        assert!(root.join("feat/untracked").exists());
+
+        remove_worktree(&repo, "feat", true).unwrap();
+
+        assert!(!root.join("feat").exists());
+        assert!(repo.find_worktree("feat").is_err());
+        assert!(get_aliases_of(&repo, "feat").is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn locked_worktree_is_only_removed_with_force() {
+        let root = get_test_dir("worktree-remove-locked");
+        let repo = init_repo(&root);
+        add_branch_worktree(&repo, &root, "feat");
+        lock_worktree(&repo, "feat", None).unwrap();
+
+        let error = remove_worktree(&repo, "feat", false).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Worktree `feat` is locked, use --force to remove it"
+        );
+        assert!(repo.find_worktree("feat").is_ok());
+
+        remove_worktree(&repo, "feat", true).unwrap();
+
+        assert!(!root.join("feat").exists());
+        assert!(repo.find_worktree("feat").is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn prune_keeps_present_and_locked_worktrees_unless_forced() {
+        let root = get_test_dir("worktree-prune");
+        let repo = init_repo(&root);
+        add_branch_worktree(&repo, &root, "gone");
+        add_branch_worktree(&repo, &root, "locked");
+        add_branch_worktree(&repo, &root, "present");
+        lock_worktree(&repo, "locked", None).unwrap();
+        fs::remove_dir_all(root.join("gone")).unwrap();
+        fs::remove_dir_all(root.join("locked")).unwrap();
+
+        let (pruned, kept) = prune_worktrees(&repo, false).unwrap();
+
+        assert_eq!(pruned, vec!["gone"]);
+        assert_eq!(kept, vec!["locked"]);
+        assert!(repo.find_worktree("gone").is_err());
+        assert!(repo.find_worktree("locked").is_ok());
+
+        let (pruned, kept) = prune_worktrees(&repo, true).unwrap();
+
+        assert_eq!(pruned, vec!["locked"]);
+        assert!(kept.is_empty());
+        assert!(repo.find_worktree("locked").is_err());
+        assert!(repo.find_worktree("present").unwrap().validate().is_ok());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+pub(crate) const GITHUB_HOST: &str = "github.com";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum TokenSource {
+    CliEnv,
+    GhTokenEnv,
+    GithubTokenEnv,
+    GhHosts,
+    GitCredential,
+}
+
+impl Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            TokenSource::CliEnv => "WORKTREE_CLI_GITHUB_TOKEN environment variable",
+            TokenSource::GhTokenEnv => "GH_TOKEN environment variable",
+            TokenSource::GithubTokenEnv => "GITHUB_TOKEN environment variable",
+            TokenSource::GhHosts => "gh CLI configuration (hosts.yml)",
+            TokenSource::GitCredential => "git credential helper",
+        };
+
+        write!(f, "{}", source)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GithubToken {
+    pub token: String,
+    pub source: TokenSource,
+}
+
+// Environment variables are checked in order of precedence, the CLI specific one first.
+// `GH_TOKEN` and `GITHUB_TOKEN` are meant for github.com, so they are never sent elsewhere
+const TOKEN_ENV_VARS: [(&str, TokenSource); 3] = [
+    ("WORKTREE_CLI_GITHUB_TOKEN", TokenSource::CliEnv),
+    ("GH_TOKEN", TokenSource::GhTokenEnv),
+    ("GITHUB_TOKEN", TokenSource::GithubTokenEnv),
+];
+
+pub(crate) fn get_github_env_token(host: &str) -> Option<GithubToken> {
+    for (var, source) in TOKEN_ENV_VARS {
+        if source != TokenSource::CliEnv && host != GITHUB_HOST {
+            continue;
+        }
+
+        if let Ok(token) = env::var(var) {
+            if !token.trim().is_empty() {
+                return Some(GithubToken {
+                    token: token.trim().to_string(),
+                    source,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+pub(crate) fn discover_github_token(host: &str) -> Option<GithubToken> {
+    if let Some(token) = get_github_env_token(host) {
+        debug!("Using GitHub token from {}", token.source);
+        return Some(token);
+    }
+
+    if let Some(token) = get_gh_hosts_token(host) {
+        debug!("Using GitHub token from {}", TokenSource::GhHosts);
+        return Some(GithubToken {
+            token,
+            source: TokenSource::GhHosts,
+        });
+    }
+
+    if let Some(token) = get_git_credential_token(host) {
+        debug!("Using GitHub token from {}", TokenSource::GitCredential);
+        return Some(GithubToken {
+            token,
+            source: TokenSource::GitCredential,
+        });
+    }
+
+    None
+}
+
+fn get_gh_config_dir() -> Option<PathBuf> {
+    if let Some(config_dir) = env::var_os("GH_CONFIG_DIR") {
+        return Some(PathBuf::from(config_dir));
+    }
+
+    if let Some(config_home) = env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(config_home).join("gh"));
+    }
+
+    if cfg!(windows) {
+        if let Some(app_data) = env::var_os("AppData") {
+            return Some(PathBuf::from(app_data).join("GitHub CLI"));
+        }
+    }
+
+    dirs::home_dir().map(|home| home.join(".config").join("gh"))
+}
+
+fn get_gh_hosts_token(host: &str) -> Option<String> {
+    let hosts_path = get_gh_config_dir()?.join("hosts.yml");
+    let contents = fs::read_to_string(&hosts_path).ok()?;
+
+    parse_gh_hosts_token(&contents, host)
+}
+
+// `hosts.yml` is a flat map of hosts, so a line based lookup is enough to find the token
+fn parse_gh_hosts_token(contents: &str, host: &str) -> Option<String> {
+    let mut in_host_section = false;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            in_host_section = line.trim_end().trim_end_matches(':') == host;
+            continue;
+        }
+
+        if in_host_section {
+            if let Some(token) = line.trim().strip_prefix("oauth_token:") {
+                let token = token.trim().trim_matches(|c| c == '"' || c == '\'');
+
+                if !token.is_empty() {
+                    return Some(token.to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+pub(crate) fn get_git_credential_token(host: &str) -> Option<String> {
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={}\n\n", host).as_bytes())
+        .ok()?;
+
+    let output = child.wait_with_output().ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .filter(|password| !password.is_empty())
+        .map(|password| password.to_string())
+}
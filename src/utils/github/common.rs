@@ -1,62 +1,67 @@
-use std::env::{self, VarError};
-
 use anyhow::{bail, Result};
 use log::info;
-use octocrab::Octocrab;
-use regex::Regex;
+use octocrab::{models::Rate, Octocrab};
+
+use super::auth::{discover_github_token, GithubToken};
 
-pub(crate) fn retrieve_github_access_token() -> Result<String, VarError> {
-    env::var("WORKTREE_CLI_GITHUB_TOKEN")
+pub(crate) fn retrieve_github_access_token(host: &str) -> Option<GithubToken> {
+    discover_github_token(host)
 }
 
-pub async fn setup_octocrab() -> Result<Octocrab, anyhow::Error> {
+pub(crate) async fn setup_octocrab_with_token(
+    access_token: Option<&GithubToken>,
+    api_url: Option<&str>,
+) -> Result<(Octocrab, Option<Rate>), anyhow::Error> {
     let mut builder = octocrab::OctocrabBuilder::new();
 
-    if let Ok(access_token) = retrieve_github_access_token() {
-        builder = builder.personal_token(access_token);
+    if let Some(api_url) = api_url {
+        builder = builder.base_uri(api_url)?;
+    }
+
+    if let Some(access_token) = access_token {
+        builder = builder.personal_token(access_token.token.clone());
         let octocrab = builder.build()?;
         match octocrab.ratelimit().get().await {
-            Ok(rate) => info!(
-                "GitHub API rate limit: {}/{}.",
-                rate.resources.core.used, rate.resources.core.limit
-            ),
+            Ok(rate) => {
+                info!(
+                    "GitHub API rate limit: {}/{}.",
+                    rate.resources.core.used, rate.resources.core.limit
+                );
+
+                Ok((octocrab, Some(rate.resources.core)))
+            }
             Err(e) => {
                 bail!(
-                "Failed to get rate limit info: {}. GitHub Personal Access Token might be invalid.",
-                e
+                "Failed to get rate limit info: {}. GitHub Personal Access Token from {} might be invalid.",
+                e,
+                access_token.source
             );
             }
         }
-        Ok(octocrab)
     } else {
-        builder.build().map_err(anyhow::Error::from)
+        Ok((builder.build()?, None))
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct GitRepoInfo {
-    pub owner: String,
-    pub repo: String,
-}
+pub(crate) async fn get_token_scopes(octocrab: &Octocrab) -> Result<Option<Vec<String>>> {
+    let response = octocrab._get("/user").await?;
+
+    if !response.status().is_success() {
+        bail!("Failed to get user info: {}", response.status());
+    }
+
+    // Fine-grained tokens do not report OAuth scopes
+    let scopes = response
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|scopes| scopes.to_str().ok())
+        .map(|scopes| {
+            scopes
+                .split(',')
+                .map(|scope| scope.trim().to_string())
+                .filter(|scope| !scope.is_empty())
+                .collect()
+        });
 
-// Helper function to parse GitHub URL
-pub(crate) fn parse_github_url(url: &str) -> Result<GitRepoInfo> {
-    let re = Regex::new(r"github\.com[:/](?P<owner>[^/]+)/(?P<repo>[^/.]+)(?:\.git)?$")?;
-
-    let caps = re
-        .captures(url)
-        .ok_or_else(|| anyhow::anyhow!("Invalid GitHub URL"))?;
-    let owner = caps
-        .name("owner")
-        .ok_or_else(|| anyhow::anyhow!("Owner not found"))?
-        .as_str();
-    let repo = caps
-        .name("repo")
-        .ok_or_else(|| anyhow::anyhow!("Repository name not found"))?
-        .as_str();
-
-    Ok(GitRepoInfo {
-        owner: owner.to_string(),
-        repo: repo.to_string(),
-    })
+    Ok(scopes)
 }
@@ -1,2 +1,2 @@
-pub(crate) mod pr;
+pub(crate) mod auth;
 pub(crate) mod common;
@@ -0,0 +1,180 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{file::write_atomically, git::common::get_common_dir};
+
+const HISTORY_FILE_NAME: &str = "worktree-cli-history.json";
+const MAX_ENTRIES: usize = 500;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct HistoryEntry {
+    pub(crate) name: String,
+    pub(crate) timestamp: i64,
+}
+
+/// Switches between worktrees (or branches in a regular repository), oldest first
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub(crate) struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug)]
+pub(crate) struct HistorySummary {
+    pub(crate) name: String,
+    pub(crate) switches: usize,
+    pub(crate) last_switch: i64,
+    pub(crate) score: f64,
+}
+
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn get_history_path(repo: &Repository) -> PathBuf {
+    get_common_dir(repo).join(HISTORY_FILE_NAME)
+}
+
+/// Name of the worktree or branch the repository is currently on
+pub(crate) fn get_current_name(repo: &Repository) -> Option<String> {
+    if repo.is_worktree() {
+        repo.path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    } else if repo.is_bare() {
+        None
+    } else {
+        repo.head()
+            .ok()
+            .and_then(|head| head.shorthand().map(str::to_string))
+    }
+}
+
+fn weight(age: i64) -> f64 {
+    match age {
+        age if age < HOUR => 4.0,
+        age if age < DAY => 2.0,
+        age if age < WEEK => 0.5,
+        _ => 0.25,
+    }
+}
+
+impl History {
+    /// A missing or unreadable store is treated as empty history
+    pub(crate) fn load(repo: &Repository) -> Self {
+        fs::read_to_string(get_history_path(repo))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, repo: &Repository) -> Result<()> {
+        write_atomically(&get_history_path(repo), &serde_json::to_string(self)?)
+            .context("Failed to write the switch history")
+    }
+
+    pub(crate) fn record(&mut self, name: &str) {
+        self.entries.push(HistoryEntry {
+            name: name.to_string(),
+            timestamp: now(),
+        });
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Most recently switched to name which is not the current one and still exists
+    pub(crate) fn previous<F>(&self, current: Option<&str>, exists: F) -> Option<String>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.entries
+            .iter()
+            .rev()
+            .map(|entry| entry.name.as_str())
+            .find(|name| Some(*name) != current && exists(name))
+            .map(str::to_string)
+    }
+
+    fn last(&self) -> Option<&str> {
+        self.entries.last().map(|entry| entry.name.as_str())
+    }
+
+    pub(crate) fn frecency(&self, name: &str) -> f64 {
+        let now = now();
+
+        self.entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .map(|entry| weight(now - entry.timestamp))
+            .sum()
+    }
+
+    /// Stable sort, so items without history keep their order
+    pub(crate) fn sort_by_frecency<T, F>(&self, items: &mut [T], name: F)
+    where
+        F: Fn(&T) -> &str,
+    {
+        items.sort_by(|a, b| {
+            self.frecency(name(b))
+                .partial_cmp(&self.frecency(name(a)))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// One summary per name, most recent switch first
+    pub(crate) fn summaries(&self) -> Vec<HistorySummary> {
+        let mut summaries: Vec<HistorySummary> = Vec::new();
+
+        for entry in self.entries.iter().rev() {
+            if let Some(summary) = summaries.iter_mut().find(|s| s.name == entry.name) {
+                summary.switches += 1;
+            } else {
+                summaries.push(HistorySummary {
+                    name: entry.name.clone(),
+                    switches: 1,
+                    last_switch: entry.timestamp,
+                    score: self.frecency(&entry.name),
+                });
+            }
+        }
+
+        summaries
+    }
+}
+
+/// Records a switch from `current`, failing to do so never fails the switch itself
+pub(crate) fn record_switch(repo: &Repository, current: Option<String>, name: &str) {
+    let mut history = History::load(repo);
+
+    // Remember where the switch came from, so `-` can go back there
+    if let Some(current) = current {
+        if current != name && history.last() != Some(current.as_str()) {
+            history.record(&current);
+        }
+    }
+
+    history.record(name);
+
+    if let Err(e) = history.save(repo) {
+        warn!("{:?}", e);
+    }
+}
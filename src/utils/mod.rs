@@ -1,4 +1,13 @@
 pub(crate) mod cli;
+pub(crate) mod doctor;
+pub(crate) mod exec;
+pub(crate) mod file;
+pub(crate) mod forge;
 pub(crate) mod git;
 pub(crate) mod github;
+pub(crate) mod history;
+pub(crate) mod registry;
+pub(crate) mod retry;
 pub(crate) mod search;
+pub(crate) mod signal;
+pub(crate) mod tui;
@@ -0,0 +1,111 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{file::write_atomically, git::common::get_common_dir};
+
+const REGISTRY_FILE_NAME: &str = "repositories.json";
+
+/// Repositories known to the CLI, stored by their common git directory
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub(crate) struct Registry {
+    repositories: Vec<PathBuf>,
+}
+
+fn get_registry_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|config_dir| config_dir.join("worktree-cli").join(REGISTRY_FILE_NAME))
+}
+
+/// Name shown for a repository, `proj` for both `proj/.git` and `proj.git`
+pub(crate) fn get_repository_name(common_dir: &Path) -> String {
+    let dir = if common_dir.ends_with(".git") {
+        common_dir.parent().unwrap_or(common_dir)
+    } else {
+        common_dir
+    };
+
+    dir.file_name()
+        .map(|name| name.to_string_lossy())
+        .map(|name| name.trim_end_matches(".git").to_string())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+impl Registry {
+    /// A missing registry is empty, an unreadable one is an error so it is never overwritten
+    pub(crate) fn load() -> Result<Self> {
+        let Some(path) = get_registry_path().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read registry at {}", path.display()))?;
+
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse registry at {}", path.display()))
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        let path = get_registry_path().context("Failed to find the config directory")?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        write_atomically(&path, &serde_json::to_string_pretty(self)?)
+            .context("Failed to write the registry")
+    }
+
+    pub(crate) fn repositories(&self) -> &[PathBuf] {
+        &self.repositories
+    }
+
+    /// Returns false when the repository was already registered
+    pub(crate) fn add(&mut self, repo: &Repository) -> bool {
+        // Drops the trailing separator git2 leaves on `.git/`
+        let common_dir = get_common_dir(repo).components().collect::<PathBuf>();
+
+        if self.repositories.contains(&common_dir) {
+            return false;
+        }
+
+        self.repositories.push(common_dir);
+        self.repositories.sort();
+
+        true
+    }
+
+    /// Removes by common dir, working directory or name
+    pub(crate) fn remove(&mut self, repository: &str) -> Result<PathBuf> {
+        let path = fs::canonicalize(repository).ok();
+
+        let Some(index) = self.repositories.iter().position(|common_dir| {
+            path.as_ref().is_some_and(|path| {
+                common_dir == path || common_dir.parent() == Some(path.as_path())
+            }) || get_repository_name(common_dir) == repository
+        }) else {
+            bail!("Repository `{}` is not registered", repository);
+        };
+
+        Ok(self.repositories.remove(index))
+    }
+}
+
+/// Registers a repository on use, failing to do so never fails the command itself
+pub(crate) fn register_repository(repo: &Repository) {
+    let result = Registry::load().and_then(|mut registry| {
+        if registry.add(repo) {
+            registry.save()?;
+        }
+
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        warn!("{:?}", e);
+    }
+}
@@ -0,0 +1,127 @@
+use std::{future::Future, time::Duration};
+
+use anyhow::{bail, Result};
+use git2::{ErrorClass, ErrorCode};
+
+use super::signal::is_cancelled;
+
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Network failures and lock files held by a concurrent git process (e.g. `index.lock`,
+/// `packed-refs.lock`) usually go away on their own. Rejected credentials and certificates
+/// don't, and retrying them could prompt for a passphrase again
+fn is_transient_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let Some(error) = cause.downcast_ref::<git2::Error>() else {
+            return false;
+        };
+
+        if matches!(error.code(), ErrorCode::Auth | ErrorCode::Certificate) {
+            return false;
+        }
+
+        matches!(error.code(), ErrorCode::Locked)
+            || matches!(
+                error.class(),
+                ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssh
+            )
+            || error.message().contains(".lock")
+    })
+}
+
+/// Runs `operation` until it succeeds, fails with a non transient error or runs out of
+/// attempts, doubling the delay between attempts
+pub(crate) async fn retry_with_backoff<T, F, Fut>(description: &str, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+
+    loop {
+        if is_cancelled() {
+            bail!("Cancelled");
+        }
+
+        match operation().await {
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient_error(&e) && !is_cancelled() => {
+                warn!(
+                    "Failed to {} ({:#}), retrying in {:?} (attempt {}/{})",
+                    description, e, backoff, attempt, MAX_ATTEMPTS
+                );
+
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Error;
+
+    use super::*;
+
+    fn is_transient(code: ErrorCode, class: ErrorClass, message: &str) -> bool {
+        is_transient_error(&anyhow::Error::from(Error::new(code, class, message)))
+    }
+
+    #[test]
+    fn network_and_lock_errors_are_transient() {
+        assert!(is_transient(
+            ErrorCode::GenericError,
+            ErrorClass::Net,
+            "connection reset"
+        ));
+        assert!(is_transient(
+            ErrorCode::GenericError,
+            ErrorClass::Http,
+            "unexpected http status code: 502"
+        ));
+        assert!(is_transient(ErrorCode::Locked, ErrorClass::Index, "locked"));
+        assert!(is_transient(
+            ErrorCode::GenericError,
+            ErrorClass::Reference,
+            "failed to lock file 'packed-refs.lock'"
+        ));
+    }
+
+    #[test]
+    fn rejected_credentials_and_certificates_are_not_transient() {
+        assert!(!is_transient(
+            ErrorCode::Auth,
+            ErrorClass::Http,
+            "too many redirects or authentication replays"
+        ));
+        assert!(!is_transient(
+            ErrorCode::Auth,
+            ErrorClass::Ssh,
+            "failed to authenticate SSH session"
+        ));
+        assert!(!is_transient(
+            ErrorCode::Certificate,
+            ErrorClass::Ssh,
+            "invalid or unknown remote ssh hostkey"
+        ));
+        assert!(!is_transient(
+            ErrorCode::Certificate,
+            ErrorClass::Http,
+            "the SSL certificate is invalid"
+        ));
+    }
+
+    #[test]
+    fn other_errors_are_not_transient() {
+        assert!(!is_transient(
+            ErrorCode::NotFound,
+            ErrorClass::Reference,
+            "reference 'refs/heads/x' not found"
+        ));
+        assert!(!is_transient_error(&anyhow::anyhow!("plain error")));
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// Conventional exit code of a process terminated by SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static CANCELLABLE_OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Ctrl-C cancels running cancellable operations, a second Ctrl-C or one outside
+/// such an operation exits right away
+pub(crate) fn install_ctrl_c_handler() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if CANCELLABLE_OPERATIONS.load(Ordering::SeqCst) == 0
+                || CANCELLED.swap(true, Ordering::SeqCst)
+            {
+                std::process::exit(INTERRUPTED_EXIT_CODE);
+            }
+
+            warn!("Cancelling, press Ctrl-C again to exit immediately");
+        }
+    });
+}
+
+pub(crate) fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+pub(crate) struct CancellableGuard;
+
+impl Drop for CancellableGuard {
+    fn drop(&mut self) {
+        CANCELLABLE_OPERATIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Marks an operation as cancellable until the returned guard is dropped
+pub(crate) fn cancellable() -> CancellableGuard {
+    CANCELLABLE_OPERATIONS.fetch_add(1, Ordering::SeqCst);
+
+    CancellableGuard
+}
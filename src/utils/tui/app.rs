@@ -0,0 +1,385 @@
+use anyhow::{bail, Result};
+use git2::Repository;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    utils::{
+        cli::add_worktree_to_repo,
+        forge::{
+            pr::{add_workspace_by_pull_requests, PrResultStatus},
+            ChangeRequestState,
+        },
+        git::{
+            commit::get_worktree_log,
+            fetch::FetchSettings,
+            status::{get_worktree_statuses, WorktreeStatus},
+            sync::{sync_worktrees, SyncStatus},
+            worktree::{
+                lock_worktree, normalize_workspace_name, remove_worktree, unlock_worktree, AddKind,
+            },
+        },
+    },
+    PRKind, PrSelection,
+};
+
+const LOG_LIMIT: usize = 200;
+const PR_JOBS: usize = 4;
+
+// Progress output would draw over the UI
+const FETCH_SETTINGS: FetchSettings = FetchSettings {
+    quiet: true,
+    skip: false,
+    full: false,
+    depth: None,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InputKind {
+    AddWorktree,
+    AddPullRequests,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Normal,
+    Input {
+        kind: InputKind,
+        value: String,
+    },
+    ConfirmRemove {
+        name: String,
+        force: bool,
+    },
+    Log {
+        title: String,
+        lines: Vec<String>,
+        scroll: usize,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    None,
+    Quit,
+    Switch(String),
+    OpenEditor(String),
+    AddWorktree(String),
+    AddPullRequests(Vec<u64>),
+    Remove { name: String, force: bool },
+    ToggleLock(String),
+    ShowLog(String),
+    Sync,
+    Refresh,
+}
+
+pub(crate) struct App {
+    pub base: Option<String>,
+    pub worktrees: Vec<WorktreeStatus>,
+    pub selected: usize,
+    pub mode: Mode,
+    pub message: Option<String>,
+}
+
+impl App {
+    pub(crate) fn new(repo: &Repository, base: Option<String>) -> Self {
+        let mut app = App {
+            base,
+            worktrees: Vec::new(),
+            selected: 0,
+            mode: Mode::Normal,
+            message: None,
+        };
+
+        app.refresh(repo);
+
+        app
+    }
+
+    pub(crate) fn refresh(&mut self, repo: &Repository) {
+        self.worktrees = get_worktree_statuses(repo, self.base.as_deref());
+        self.selected = self.selected.min(self.worktrees.len().saturating_sub(1));
+    }
+
+    pub(crate) fn selected_worktree(&self) -> Option<&WorktreeStatus> {
+        self.worktrees.get(self.selected)
+    }
+
+    fn selected_name(&self) -> Option<String> {
+        self.selected_worktree()
+            .map(|worktree| worktree.name.clone())
+    }
+
+    /// Updates the UI state for a key press and returns what should be done outside of it
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Action::Quit;
+        }
+
+        match &mut self.mode {
+            Mode::Normal => self.handle_normal_key(key),
+            Mode::Input { kind, value } => match key.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    Action::None
+                }
+                KeyCode::Backspace => {
+                    value.pop();
+                    Action::None
+                }
+                KeyCode::Char(c) => {
+                    value.push(c);
+                    Action::None
+                }
+                KeyCode::Enter => {
+                    let (kind, value) = (*kind, value.trim().to_string());
+                    self.mode = Mode::Normal;
+
+                    self.submit_input(kind, value)
+                }
+                _ => Action::None,
+            },
+            Mode::ConfirmRemove { name, force } => {
+                let action = match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => Action::Remove {
+                        name: name.clone(),
+                        force: *force,
+                    },
+                    _ => Action::None,
+                };
+
+                self.mode = Mode::Normal;
+                action
+            }
+            Mode::Log { lines, scroll, .. } => {
+                match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        *scroll = (*scroll + 1).min(lines.len().saturating_sub(1));
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.mode = Mode::Normal,
+                    _ => {}
+                }
+
+                Action::None
+            }
+        }
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) -> Action {
+        self.message = None;
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.worktrees.len().saturating_sub(1));
+                Action::None
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                Action::None
+            }
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.selected = 0;
+                Action::None
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                self.selected = self.worktrees.len().saturating_sub(1);
+                Action::None
+            }
+            KeyCode::Char('a') => {
+                self.start_input(InputKind::AddWorktree);
+                Action::None
+            }
+            KeyCode::Char('p') => {
+                self.start_input(InputKind::AddPullRequests);
+                Action::None
+            }
+            KeyCode::Char('s') => Action::Sync,
+            KeyCode::Char('r') => Action::Refresh,
+            code => {
+                let Some(name) = self.selected_name() else {
+                    return Action::None;
+                };
+
+                match code {
+                    KeyCode::Enter => Action::Switch(name),
+                    KeyCode::Char('e') => Action::OpenEditor(name),
+                    KeyCode::Char('v') => Action::ShowLog(name),
+                    KeyCode::Char('l') => Action::ToggleLock(name),
+                    KeyCode::Char('d') | KeyCode::Char('D') => {
+                        self.mode = Mode::ConfirmRemove {
+                            name,
+                            force: code == KeyCode::Char('D'),
+                        };
+                        Action::None
+                    }
+                    _ => Action::None,
+                }
+            }
+        }
+    }
+
+    fn start_input(&mut self, kind: InputKind) {
+        self.mode = Mode::Input {
+            kind,
+            value: String::new(),
+        };
+    }
+
+    fn submit_input(&mut self, kind: InputKind, value: String) -> Action {
+        if value.is_empty() {
+            return Action::None;
+        }
+
+        match kind {
+            InputKind::AddWorktree => Action::AddWorktree(value),
+            InputKind::AddPullRequests => {
+                let pr_numbers = value
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|number| !number.is_empty())
+                    .map(|number| number.trim_start_matches('#').parse::<u64>())
+                    .collect::<Result<Vec<u64>, _>>();
+
+                match pr_numbers {
+                    Ok(pr_numbers) => Action::AddPullRequests(pr_numbers),
+                    Err(_) => {
+                        self.message = Some(format!("Invalid PR numbers `{}`", value));
+                        Action::None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs an action which stays inside the UI, failures are shown as the message
+    pub(crate) async fn perform(&mut self, repo: &Repository, action: Action) {
+        let result = match action {
+            Action::AddWorktree(name) => self.add_worktree(repo, &name),
+            Action::AddPullRequests(pr_numbers) => self.add_pull_requests(repo, pr_numbers).await,
+            Action::Remove { name, force } => {
+                remove_worktree(repo, &name, force).map(|_| format!("Removed worktree `{name}`"))
+            }
+            Action::ToggleLock(name) => self.toggle_lock(repo, &name),
+            Action::ShowLog(name) => match get_worktree_log(repo, &name, LOG_LIMIT) {
+                Ok(lines) => {
+                    self.mode = Mode::Log {
+                        title: format!("Log of {name}"),
+                        lines,
+                        scroll: 0,
+                    };
+
+                    return;
+                }
+                Err(e) => Err(e.into()),
+            },
+            Action::Sync => self.sync(repo),
+            Action::Refresh => Ok(String::from("Refreshed")),
+            _ => return,
+        };
+
+        self.message = Some(match result {
+            Ok(message) => message,
+            Err(e) => format!("Error: {:#}", e),
+        });
+
+        self.refresh(repo);
+    }
+
+    fn add_worktree(&mut self, repo: &Repository, name: &str) -> Result<String> {
+        if name.contains('/') {
+            bail!("Cannot add a worktree with a '/' in the name")
+        }
+
+        let (_, add_kind) = add_worktree_to_repo(repo, name, &FETCH_SETTINGS)?;
+
+        self.select(repo, name);
+
+        Ok(match add_kind {
+            AddKind::Added => format!("Added worktree `{name}`"),
+            AddKind::Existed => format!("Worktree `{name}` already exists"),
+        })
+    }
+
+    async fn add_pull_requests(
+        &mut self,
+        repo: &Repository,
+        pr_numbers: Vec<u64>,
+    ) -> Result<String> {
+        let pr_results = add_workspace_by_pull_requests(
+            repo,
+            ChangeRequestState::Open,
+            PRKind::All,
+            PrSelection::All,
+            pr_numbers,
+            FETCH_SETTINGS,
+            PR_JOBS,
+        )
+        .await?;
+
+        let summary = pr_results
+            .iter()
+            .map(|pr_result| match &pr_result.error {
+                Some(error) => format!("#{} failed: {}", pr_result.number, error),
+                None => format!("#{} {}", pr_result.number, pr_result.status),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        if let Some(pr_result) = pr_results
+            .iter()
+            .find(|pr_result| pr_result.status != PrResultStatus::Failed)
+        {
+            self.select(repo, &normalize_workspace_name(&pr_result.branch));
+        }
+
+        Ok(summary)
+    }
+
+    fn toggle_lock(&self, repo: &Repository, name: &str) -> Result<String> {
+        let locked = self
+            .worktrees
+            .iter()
+            .any(|worktree| worktree.name == name && worktree.locked.is_some());
+
+        if locked {
+            unlock_worktree(repo, name)?;
+            Ok(format!("Unlocked worktree `{name}`"))
+        } else {
+            lock_worktree(repo, name, None)?;
+            Ok(format!("Locked worktree `{name}`"))
+        }
+    }
+
+    fn sync(&self, repo: &Repository) -> Result<String> {
+        let sync_results = sync_worktrees(repo, false, &FETCH_SETTINGS)?;
+
+        let count = |status: SyncStatus| {
+            sync_results
+                .iter()
+                .filter(|sync_result| sync_result.status == status)
+                .count()
+        };
+
+        Ok(format!(
+            "Synced: {} updated, {} up to date, {} skipped, {} diverged, {} failed",
+            count(SyncStatus::Updated),
+            count(SyncStatus::UpToDate),
+            count(SyncStatus::Skipped),
+            count(SyncStatus::Diverged),
+            count(SyncStatus::Failed)
+        ))
+    }
+
+    /// Refreshes the list and moves the selection to the worktree `name`
+    fn select(&mut self, repo: &Repository, name: &str) {
+        self.refresh(repo);
+
+        if let Some(index) = self
+            .worktrees
+            .iter()
+            .position(|worktree| worktree.name == name)
+        {
+            self.selected = index;
+        }
+    }
+}
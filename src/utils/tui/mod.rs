@@ -0,0 +1,336 @@
+use std::{
+    env,
+    io::{self, Stderr},
+    path::Path,
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use git2::Repository;
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    crossterm::{
+        event::{self, Event, KeyEventKind},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    Terminal,
+};
+
+use super::git::worktree::get_worktree_path_by_name;
+
+use app::{Action, App};
+
+pub(crate) mod app;
+pub(crate) mod ui;
+
+// Stdout is left for the `cd` command printed on exit, which the shell evaluates
+fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stderr>>> {
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+
+    Ok(Terminal::new(CrosstermBackend::new(io::stderr()))?)
+}
+
+fn restore_terminal() -> Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stderr(), LeaveAlternateScreen)?;
+
+    Ok(())
+}
+
+fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+
+    let mut args = editor.split_whitespace();
+    let program = args.next().context("No editor configured")?;
+
+    restore_terminal()?;
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .stdout(io::stderr())
+        .status();
+
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+
+    let status = status.with_context(|| format!("Failed to run `{}`", editor))?;
+
+    if !status.success() {
+        anyhow::bail!("`{}` exited with {}", editor, status);
+    }
+
+    Ok(())
+}
+
+/// Draws the app and handles key presses from `next_event` until the user quits, returns the
+/// command to switch to the chosen worktree, if any
+pub(crate) async fn run_app<B, E>(
+    terminal: &mut Terminal<B>,
+    repo: &Repository,
+    app: &mut App,
+    mut next_event: E,
+) -> Result<Option<String>>
+where
+    B: Backend,
+    E: FnMut() -> io::Result<Event>,
+{
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        let Event::Key(key) = next_event()? else {
+            continue;
+        };
+
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match app.handle_key(key) {
+            Action::None => {}
+            Action::Quit => return Ok(None),
+            Action::Switch(name) => {
+                let worktree_path = get_worktree_path_by_name(repo, &name)?;
+
+                return Ok(Some(format!("cd {}", worktree_path)));
+            }
+            Action::OpenEditor(name) => {
+                let result = get_worktree_path_by_name(repo, &name)
+                    .and_then(|worktree_path| open_in_editor(Path::new(&worktree_path)));
+
+                if let Err(e) = result {
+                    app.message = Some(format!("Error: {:#}", e));
+                }
+
+                app.refresh(repo);
+                terminal.clear()?;
+            }
+            action => {
+                app.message = Some(String::from("Working..."));
+                terminal.draw(|frame| ui::draw(frame, app))?;
+
+                app.perform(repo, action).await;
+
+                // Log output of the action may have been written over the UI
+                terminal.clear()?;
+            }
+        }
+    }
+}
+
+pub(crate) async fn run_tui(repo: &Repository, base: Option<String>) -> Result<Option<String>> {
+    let mut app = App::new(repo, base);
+    let mut terminal = setup_terminal()?;
+
+    let result = run_app(&mut terminal, repo, &mut app, event::read).await;
+
+    restore_terminal()?;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use git2::{Signature, Time, WorktreeAddOptions};
+    use ratatui::{
+        backend::TestBackend,
+        buffer::Buffer,
+        crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+        style::Modifier,
+    };
+
+    use super::*;
+
+    /// A repository with the worktrees `beta` and `alpha`, listed in that order as `beta` has the
+    /// newer commit
+    fn create_repo(test_name: &str) -> (PathBuf, Repository) {
+        let root =
+            env::temp_dir().join(format!("worktree-cli-tui-{}-{}", test_name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let repo = Repository::init(root.join("repo")).unwrap();
+        let tree_id = repo.treebuilder(None).unwrap().write().unwrap();
+
+        for (name, time) in [("alpha", 1_000), ("beta", 2_000)] {
+            let signature =
+                Signature::new("test", "test@example.com", &Time::new(time, 0)).unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let commit_id = repo
+                .commit(
+                    None,
+                    &signature,
+                    &signature,
+                    &format!("Commit on {name}"),
+                    &tree,
+                    &[],
+                )
+                .unwrap();
+            let branch = repo
+                .branch(name, &repo.find_commit(commit_id).unwrap(), false)
+                .unwrap();
+
+            repo.worktree(
+                name,
+                &root.join(name),
+                Some(WorktreeAddOptions::new().reference(Some(branch.get()))),
+            )
+            .unwrap();
+        }
+
+        (root, repo)
+    }
+
+    /// Runs the app on a test terminal with the given key presses, the input ends after them
+    async fn run(repo: &Repository, keys: Vec<KeyEvent>) -> (Result<Option<String>>, App, Buffer) {
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        let mut app = App::new(repo, None);
+        let mut keys = keys.into_iter();
+
+        let result = run_app(&mut terminal, repo, &mut app, || {
+            keys.next()
+                .map(Event::Key)
+                .ok_or_else(|| io::Error::other("No more key presses"))
+        })
+        .await;
+
+        (result, app, terminal.backend().buffer().clone())
+    }
+
+    fn get_lines(buffer: &Buffer) -> Vec<String> {
+        buffer
+            .content
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl_c() -> KeyEvent {
+        KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+    }
+
+    #[tokio::test]
+    async fn enter_switches_to_the_selected_worktree() {
+        let (root, repo) = create_repo("switch");
+
+        let (result, _, buffer) = run(&repo, vec![key(KeyCode::Down), key(KeyCode::Enter)]).await;
+
+        let command = result.unwrap().unwrap();
+        assert!(command.starts_with("cd "), "{command}");
+        assert!(Path::new(command.trim_start_matches("cd ")).ends_with("alpha"));
+
+        let lines = get_lines(&buffer);
+        let beta_row = lines
+            .iter()
+            .position(|line| line.contains("│beta"))
+            .unwrap();
+        let alpha_row = lines
+            .iter()
+            .position(|line| line.contains("│alpha"))
+            .unwrap();
+        assert!(beta_row < alpha_row, "{lines:#?}");
+        assert!(lines.iter().any(|line| line.contains("enter switch")));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn selected_row_is_highlighted() {
+        let (root, repo) = create_repo("highlight");
+
+        let (result, app, buffer) = run(
+            &repo,
+            vec![key(KeyCode::Char('j')), key(KeyCode::Char('q'))],
+        )
+        .await;
+
+        assert!(result.unwrap().is_none());
+        assert_eq!(app.selected_worktree().unwrap().name, "alpha");
+
+        let lines = get_lines(&buffer);
+        let is_highlighted = |name: &str| {
+            let y = lines
+                .iter()
+                .position(|line| line.contains(&format!("│{name}")))
+                .unwrap();
+
+            buffer.content[buffer.index_of(1, y as u16)]
+                .modifier
+                .contains(Modifier::REVERSED)
+        };
+
+        assert!(is_highlighted("alpha"));
+        assert!(!is_highlighted("beta"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn remove_asks_for_confirmation_first() {
+        let (root, repo) = create_repo("remove");
+
+        let (result, _, buffer) = run(&repo, vec![key(KeyCode::Char('d')), ctrl_c()]).await;
+
+        assert!(result.unwrap().is_none());
+
+        let lines = get_lines(&buffer);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.contains("Remove `beta`? (y/n)")),
+            "{lines:#?}"
+        );
+        assert!(repo.find_worktree("beta").is_ok());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn log_of_the_selected_worktree_is_shown() {
+        let (root, repo) = create_repo("log");
+
+        let (result, _, buffer) = run(&repo, vec![key(KeyCode::Char('v')), ctrl_c()]).await;
+
+        assert!(result.unwrap().is_none());
+
+        let lines = get_lines(&buffer);
+        assert!(
+            lines.iter().any(|line| line.contains("Log of beta")),
+            "{lines:#?}"
+        );
+        assert!(lines.iter().any(|line| line.contains("Commit on beta")));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn worktree_without_status_is_listed_with_its_error() {
+        let (root, repo) = create_repo("error");
+        fs::write(repo.path().join("worktrees/beta/index"), "garbage").unwrap();
+
+        let (result, _, buffer) = run(&repo, vec![ctrl_c()]).await;
+
+        assert!(result.unwrap().is_none());
+
+        let lines = get_lines(&buffer);
+        let beta = lines.iter().find(|line| line.contains("│beta")).unwrap();
+        assert!(beta.contains(" ? "), "{beta}");
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("Error: failed to read index")),
+            "{lines:#?}"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
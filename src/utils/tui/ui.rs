@@ -0,0 +1,206 @@
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState,
+    },
+    Frame,
+};
+
+use crate::utils::git::status::{format_ahead_behind, format_count, WorktreeStatus};
+
+use super::app::{App, InputKind, Mode};
+
+const HELP: &str = "enter switch  a add  p add PR  d remove  D force remove  l lock/unlock  s sync  e edit  v log  r refresh  q quit";
+
+fn get_row(worktree: &WorktreeStatus) -> Row<'_> {
+    let style =
+        if worktree.error.is_some() || worktree.conflicted > 0 || worktree.operation.is_some() {
+            Style::default().fg(Color::Red)
+        } else if worktree.staged + worktree.unstaged + worktree.untracked > 0 {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+
+    // The counts of a worktree whose status failed are unknown, not zero
+    let known = |value: String| {
+        if worktree.error.is_some() {
+            String::from("?")
+        } else {
+            value
+        }
+    };
+
+    Row::new([
+        Cell::from(worktree.name.as_str()),
+        Cell::from(worktree.head_label()),
+        Cell::from(known(format_count(worktree.staged))),
+        Cell::from(known(format_count(worktree.unstaged))),
+        Cell::from(known(format_count(worktree.untracked))),
+        Cell::from(known(format_count(worktree.conflicted))),
+        Cell::from(known(format_count(worktree.stashes))),
+        Cell::from(known(format_ahead_behind(&worktree.upstream))),
+        Cell::from(known(format_ahead_behind(&worktree.base))),
+        Cell::from(known(worktree.operation.unwrap_or("-").to_string())),
+        Cell::from(if worktree.locked.is_some() {
+            "locked"
+        } else {
+            "-"
+        }),
+    ])
+    .style(style)
+}
+
+fn draw_worktrees(frame: &mut Frame, app: &App, area: Rect) {
+    let base_header = format!("Base ({})", app.base.as_deref().unwrap_or("none"));
+
+    let header = Row::new([
+        "Worktree",
+        "Branch",
+        "Staged",
+        "Unstaged",
+        "Untracked",
+        "Conflicts",
+        "Stashes",
+        "Upstream",
+        &base_header,
+        "In progress",
+        "Lock",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let widths = [
+        Constraint::Fill(2),
+        Constraint::Fill(2),
+        Constraint::Length(6),
+        Constraint::Length(8),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(7),
+        Constraint::Length(9),
+        Constraint::Length(base_header.chars().count().max(9) as u16),
+        Constraint::Length(11),
+        Constraint::Length(6),
+    ];
+
+    let table = Table::new(app.worktrees.iter().map(get_row), widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(" Worktrees "))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::default().with_selected(Some(app.selected));
+
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn get_popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn draw_popup(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+
+    match &app.mode {
+        Mode::Normal => {}
+        Mode::Input { kind, value } => {
+            let title = match kind {
+                InputKind::AddWorktree => " New worktree name ",
+                InputKind::AddPullRequests => " PR numbers (e.g. 12 34) ",
+            };
+
+            let popup = get_popup_area(area, 60, 3);
+
+            frame.render_widget(Clear, popup);
+            frame.render_widget(
+                Paragraph::new(value.as_str())
+                    .block(Block::default().borders(Borders::ALL).title(title)),
+                popup,
+            );
+            frame.set_cursor_position((popup.x + 1 + value.chars().count() as u16, popup.y + 1));
+        }
+        Mode::ConfirmRemove { name, force } => {
+            let text = if *force {
+                format!(
+                    "Force remove `{}` with its uncommitted changes? (y/n)",
+                    name
+                )
+            } else {
+                format!("Remove `{}`? (y/n)", name)
+            };
+
+            let popup = get_popup_area(area, text.chars().count() as u16 + 4, 3);
+
+            frame.render_widget(Clear, popup);
+            frame.render_widget(
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL)),
+                popup,
+            );
+        }
+        Mode::Log {
+            title,
+            lines,
+            scroll,
+        } => {
+            let popup = get_popup_area(
+                area,
+                area.width.saturating_sub(8),
+                area.height.saturating_sub(4),
+            );
+
+            let list = List::new(lines.iter().map(|line| ListItem::new(line.as_str())))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" {} ", title)),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+            let mut state = ListState::default().with_selected(Some(*scroll));
+
+            frame.render_widget(Clear, popup);
+            frame.render_stateful_widget(list, popup, &mut state);
+        }
+    }
+}
+
+pub(crate) fn draw(frame: &mut Frame, app: &App) {
+    let [worktrees_area, message_area, help_area] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_worktrees(frame, app, worktrees_area);
+
+    // Without a message of its own, the line explains why the selected worktree has no status
+    let message = app.message.clone().or_else(|| {
+        app.selected_worktree()
+            .and_then(|worktree| worktree.error.as_ref())
+            .map(|error| format!("Error: {}", error))
+    });
+
+    if let Some(message) = message {
+        frame.render_widget(Paragraph::new(message), message_area);
+    }
+
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            HELP,
+            Style::default().fg(Color::DarkGray),
+        ))),
+        help_area,
+    );
+
+    draw_popup(frame, app);
+}